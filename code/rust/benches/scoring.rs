@@ -0,0 +1,97 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use icfpc2023::io::{self, Solution, Task};
+use icfpc2023::{optimizer, score};
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::time::Duration;
+
+// Problems by musicians x attendees: 5 x 100, 144 x 700 with pillars, 1059 x 1000
+const SMALL_PROBLEM: usize = 42;
+const MEDIUM_PROBLEM: usize = 80;
+const HUGE_PROBLEM: usize = 1;
+// Same frozen solutions as the golden test, so that timings do not change with ../../solutions
+const SOLUTIONS_DIR: &str = "tests/fixtures/solutions";
+
+fn load(i: usize) -> (Task, Solution) {
+    let task = io::read(&format!("../../data/problem-{i}.json"));
+    let solution = io::read_solution(&format!("{SOLUTIONS_DIR}/problem-{i}.json"));
+    (task, solution)
+}
+
+fn problem_name(i: usize) -> String {
+    let (task, _) = load(i);
+    format!(
+        "problem-{i} ({}x{})",
+        task.musicians.len(),
+        task.attendees.len()
+    )
+}
+
+fn scoring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scoring");
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(10));
+
+    for i in [SMALL_PROBLEM, MEDIUM_PROBLEM, HUGE_PROBLEM] {
+        let (task, solution) = load(i);
+        let name = problem_name(i);
+        let visibility = score::calc_visibility_fast(&task, &solution);
+
+        group.bench_with_input(
+            BenchmarkId::new("calc_visibility", &name),
+            &(&task, &solution),
+            |b, (task, solution)| b.iter(|| score::calc_visibility(task, solution)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("calc_visibility_fast", &name),
+            &(&task, &solution),
+            |b, (task, solution)| b.iter(|| score::calc_visibility_fast(task, solution)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("calc", &name),
+            &(&task, &solution, &visibility),
+            |b, (task, solution, visibility)| b.iter(|| score::calc(task, solution, visibility)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("calc_ex", &name),
+            &(&task, &solution, &visibility),
+            |b, (task, solution, visibility)| b.iter(|| score::calc_ex(task, solution, visibility)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("optimize_placements_greedy", &name),
+            &(&task, &solution, &visibility),
+            |b, (task, solution, visibility)| {
+                b.iter(|| optimizer::optimize_placements_greedy(task, solution, visibility))
+            },
+        );
+    }
+    group.finish();
+}
+
+// One call of every optimizer, huge problems are too slow for that
+fn optimizers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("optimizers");
+    group.sample_size(10);
+
+    for i in [SMALL_PROBLEM, MEDIUM_PROBLEM] {
+        let (task, solution) = load(i);
+        let name = problem_name(i);
+        let visibility = score::calc_visibility_fast(&task, &solution);
+
+        for (optimizer, optimizer_name) in optimizer::ALL_OPTIMIZERS {
+            group.bench_with_input(
+                BenchmarkId::new(*optimizer_name, &name),
+                &(&task, &solution, &visibility),
+                |b, (task, solution, visibility)| {
+                    let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+                    b.iter(|| optimizer(task, solution, visibility, &mut rng))
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, scoring, optimizers);
+criterion_main!(benches);
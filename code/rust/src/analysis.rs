@@ -0,0 +1,272 @@
+use crate::geom::Point;
+use crate::io::{Task, MUSICIAN_RADIUS};
+use crate::score::calc_points_visibility;
+use anyhow::Result;
+use serde::Serialize;
+
+const ANGLE_SECTORS: usize = 8;
+// stage points per side used to estimate pillar shadows
+const SIGHTLINE_GRID: usize = 8;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TasteStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub negative_fraction: f64,
+    // sum of positive tastes, how much instrument can get with perfect placement
+    pub positive_sum: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TaskStats {
+    pub musicians: usize,
+    pub instruments: usize,
+    pub attendees: usize,
+    pub pillars: usize,
+    pub stage_area_fraction: f64,
+    // how many musicians fit on stage in hex packing compared to the number of musicians
+    pub stage_capacity_ratio: f64,
+    pub musicians_per_instrument: Vec<usize>,
+    pub tastes: Vec<TasteStats>,
+    pub negative_taste_fraction: f64,
+    // fraction of attendees in every sector around stage center, first sector starts at angle -pi
+    pub attendee_sectors: Vec<f64>,
+    // fraction of attendees by stage side they are facing: left, right, bottom, top
+    pub attendee_sides: [f64; 4],
+    // average fraction of attendees hidden by pillars from points on stage
+    pub pillar_blocked_fraction: f64,
+    // problems which do not break the rest of the code but look suspicious
+    pub warnings: Vec<String>,
+}
+
+fn taste_stats(values: &mut [f64]) -> TasteStats {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let len = values.len().max(1) as f64;
+    TasteStats {
+        min: values.first().copied().unwrap_or(0.0),
+        max: values.last().copied().unwrap_or(0.0),
+        mean: values.iter().sum::<f64>() / len,
+        median: values.get(values.len() / 2).copied().unwrap_or(0.0),
+        negative_fraction: values.iter().filter(|t| **t < 0.0).count() as f64 / len,
+        positive_sum: values.iter().filter(|t| **t > 0.0).sum(),
+    }
+}
+
+fn stage_center(task: &Task) -> Point {
+    Point {
+        x: task.stage_left() + task.stage_width / 2.0,
+        y: task.stage_bottom() + task.stage_height / 2.0,
+    }
+}
+
+fn warnings(task: &Task) -> Vec<String> {
+    let mut res = vec![];
+    let inner_width = task.stage_width - 2.0 * MUSICIAN_RADIUS;
+    let inner_height = task.stage_height - 2.0 * MUSICIAN_RADIUS;
+    if inner_width < 0.0 || inner_height < 0.0 {
+        res.push("Stage is too small for any musician".to_string());
+    }
+    let on_stage = task
+        .attendees
+        .iter()
+        .filter(|a| {
+            a.x > task.stage_left()
+                && a.x < task.stage_right()
+                && a.y > task.stage_bottom()
+                && a.y < task.stage_top()
+        })
+        .count();
+    if on_stage > 0 {
+        res.push(format!("{on_stage} attendees are on stage"));
+    }
+    let outside = task
+        .pillars
+        .iter()
+        .filter(|p| {
+            p.center.0 < task.room_left()
+                || p.center.1 < task.room_bottom()
+                || p.center.0 > task.room_right()
+                || p.center.1 > task.room_top()
+        })
+        .count();
+    if outside > 0 {
+        res.push(format!("{outside} pillars are outside of the room"));
+    }
+    let unused = (0..task.instruments_len())
+        .filter(|inst| !task.musicians.contains(inst))
+        .count();
+    if unused > 0 {
+        res.push(format!("{unused} instruments have no musicians"));
+    }
+    res
+}
+
+fn pillar_blocked_fraction(task: &Task) -> f64 {
+    if task.pillars.is_empty() {
+        return 0.0;
+    }
+    let (left, bottom) = (
+        task.stage_left() + MUSICIAN_RADIUS,
+        task.stage_bottom() + MUSICIAN_RADIUS,
+    );
+    let width = (task.stage_width - 2.0 * MUSICIAN_RADIUS).max(0.0);
+    let height = (task.stage_height - 2.0 * MUSICIAN_RADIUS).max(0.0);
+    let points = (0..SIGHTLINE_GRID)
+        .flat_map(|i| {
+            (0..SIGHTLINE_GRID).map(move |j| Point {
+                x: left + width * i as f64 / (SIGHTLINE_GRID - 1) as f64,
+                y: bottom + height * j as f64 / (SIGHTLINE_GRID - 1) as f64,
+            })
+        })
+        .collect::<Vec<_>>();
+    let visibility = calc_points_visibility(task, &[], &points);
+    let blocked = visibility
+        .iter()
+        .map(|row| row.iter().filter(|v| !**v).count())
+        .sum::<usize>();
+    blocked as f64 / (points.len() * task.attendees.len()) as f64
+}
+
+pub fn analyse(task: &Task) -> Result<TaskStats> {
+    task.check()?;
+    let instruments = task.instruments_len();
+    let attendees = task.attendees.len() as f64;
+
+    let tastes = (0..instruments)
+        .map(|inst| {
+            let mut values = task
+                .attendees
+                .iter()
+                .map(|a| a.tastes[inst])
+                .collect::<Vec<_>>();
+            taste_stats(&mut values)
+        })
+        .collect::<Vec<_>>();
+    let negative_taste_fraction = task
+        .attendees
+        .iter()
+        .flat_map(|a| a.tastes.iter())
+        .filter(|t| **t < 0.0)
+        .count() as f64
+        / (attendees * instruments as f64);
+
+    let center = stage_center(task);
+    let mut attendee_sectors = vec![0.0; ANGLE_SECTORS];
+    let mut attendee_sides = [0.0; 4];
+    for a in &task.attendees {
+        let angle = (a.coord() - center).atan2() + std::f64::consts::PI;
+        let sector = ((angle / std::f64::consts::TAU * ANGLE_SECTORS as f64) as usize)
+            .min(ANGLE_SECTORS - 1);
+        attendee_sectors[sector] += 1.0 / attendees;
+
+        let distances = [
+            (task.stage_left() - a.x).max(0.0),
+            (a.x - task.stage_right()).max(0.0),
+            (task.stage_bottom() - a.y).max(0.0),
+            (a.y - task.stage_top()).max(0.0),
+        ];
+        let side = (0..4)
+            .max_by(|i, j| distances[*i].partial_cmp(&distances[*j]).unwrap())
+            .unwrap();
+        attendee_sides[side] += 1.0 / attendees;
+    }
+
+    let mut musicians_per_instrument = vec![0; instruments];
+    for inst in &task.musicians {
+        musicians_per_instrument[*inst] += 1;
+    }
+    let hex_cell_area = MUSICIAN_RADIUS * MUSICIAN_RADIUS * 60.0f64.to_radians().sin();
+    let inner_area = (task.stage_width - 2.0 * MUSICIAN_RADIUS).max(0.0)
+        * (task.stage_height - 2.0 * MUSICIAN_RADIUS).max(0.0);
+
+    Ok(TaskStats {
+        musicians: task.musicians.len(),
+        instruments,
+        attendees: task.attendees.len(),
+        pillars: task.pillars.len(),
+        stage_area_fraction: task.stage_width * task.stage_height
+            / (task.room_width * task.room_height),
+        stage_capacity_ratio: inner_area / hex_cell_area / task.musicians.len().max(1) as f64,
+        musicians_per_instrument,
+        tastes,
+        negative_taste_fraction,
+        attendee_sectors,
+        attendee_sides,
+        pillar_blocked_fraction: pillar_blocked_fraction(task),
+        warnings: warnings(task),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::analyse;
+    use crate::io::{Attendee, Pillar, Task};
+
+    fn task() -> Task {
+        Task {
+            room_width: 100.0,
+            room_height: 100.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 30.0,
+            stage_height: 30.0,
+            stage_bottom_left: (35.0, 35.0),
+            musicians: vec![0, 1, 1],
+            attendees: vec![
+                Attendee {
+                    x: 50.0,
+                    y: 10.0,
+                    tastes: vec![100.0, -50.0],
+                },
+                Attendee {
+                    x: 50.0,
+                    y: 90.0,
+                    tastes: vec![-10.0, 30.0],
+                },
+            ],
+            pillars: vec![],
+        }
+    }
+
+    #[test]
+    fn test_analyse_stats() {
+        let stats = analyse(&task()).unwrap();
+        assert_eq!(stats.instruments, 2);
+        assert_eq!(stats.musicians_per_instrument, vec![1, 2]);
+        assert_eq!(stats.negative_taste_fraction, 0.5);
+        assert_eq!(stats.tastes[0].max, 100.0);
+        assert_eq!(stats.tastes[1].min, -50.0);
+        assert_eq!(stats.attendee_sides, [0.0, 0.0, 0.5, 0.5]);
+        assert_eq!(stats.pillar_blocked_fraction, 0.0);
+        assert!(stats.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_analyse_pillar_blocks_side() {
+        let mut task = task();
+        // pillar between stage and the bottom attendee
+        task.pillars.push(Pillar {
+            center: (50.0, 22.0),
+            radius: 8.0,
+        });
+        let stats = analyse(&task).unwrap();
+        assert!(stats.pillar_blocked_fraction > 0.0);
+        assert!(stats.pillar_blocked_fraction <= 0.5);
+    }
+
+    #[test]
+    fn test_analyse_rejects_broken_task() {
+        let mut broken = task();
+        broken.attendees[1].tastes.pop();
+        assert!(analyse(&broken).is_err());
+
+        let mut broken = task();
+        broken.musicians.push(2);
+        assert!(analyse(&broken).is_err());
+
+        let mut broken = task();
+        broken.attendees.clear();
+        assert!(analyse(&broken).is_err());
+    }
+}
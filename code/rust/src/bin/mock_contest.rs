@@ -0,0 +1,56 @@
+// Minimal imitation of the contest api for testing submit and fetch subcommands:
+// GET /problem?problem_id=N serves tasks from data dir, POST /submission accepts anything valid.
+#[macro_use]
+extern crate rouille;
+
+use icfpc2023::storage::DATA_DIR;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(serde::Deserialize)]
+struct Submission {
+    problem_id: usize,
+    contents: String,
+}
+
+fn main() {
+    let port = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "8081".to_string());
+    let submissions = AtomicUsize::new(0);
+
+    println!("Starting mock contest api on port {port}");
+    rouille::start_server(format!("0.0.0.0:{port}"), move |request| {
+        router!(request,
+            (GET) (/problem) => {
+                let problem = request.get_param("problem_id").and_then(|id| id.parse::<usize>().ok());
+                match problem.map(|id| std::fs::read_to_string(format!("{DATA_DIR}/problem-{id}.json"))) {
+                    Some(Ok(task)) => rouille::Response::json(&serde_json::json!({ "Success": task })),
+                    _ => rouille::Response::json(&serde_json::json!({ "Failure": "Problem not found" }))
+                        .with_status_code(404),
+                }
+            },
+
+            (POST) (/submission) => {
+                if request.header("authorization").is_none() {
+                    return rouille::Response::text("Missing authorization").with_status_code(401);
+                }
+                let mut body = String::new();
+                let submission = request
+                    .data()
+                    .and_then(|mut data| std::io::Read::read_to_string(&mut data, &mut body).ok())
+                    .and_then(|_| serde_json::from_str::<Submission>(&body).ok())
+                    .filter(|s| serde_json::from_str::<serde_json::Value>(&s.contents).is_ok());
+                match submission {
+                    Some(submission) => {
+                        let id = submissions.fetch_add(1, Ordering::SeqCst);
+                        println!("Got submission {id} for problem {}", submission.problem_id);
+                        rouille::Response::json(&format!("mock-{id}"))
+                    }
+                    None => rouille::Response::text("Invalid submission").with_status_code(400),
+                }
+            },
+
+            _ => rouille::Response::empty_404()
+        )
+    });
+}
@@ -1,5 +1,7 @@
-use crate::score::{calc_ex, calc_visibility_fast};
-use crate::{get_manual_solution, read_task, write_manual_solution, write_optimal_solution};
+use icfpc2023::score::{calc_ex, calc_visibility_fast};
+use icfpc2023::storage::{
+    get_manual_solution, read_task, write_manual_solution, write_optimal_solution,
+};
 use rouille;
 
 pub fn start_server() {
@@ -11,7 +13,7 @@ pub fn start_server() {
                 let task = read_task(id);
                 let visibility = calc_visibility_fast(&task, &solution);
                 let res = calc_ex(&task, &solution, &visibility);
-                write_optimal_solution(&task, &solution, res.score, id);
+                write_optimal_solution(&task, &solution, res.score, id, "manual", vec![]);
                 write_manual_solution(&solution, id);
                 rouille::Response::text(serde_json::to_string(&res).expect("Could not format score_ex json"))
             },
@@ -0,0 +1,9 @@
+#[macro_use]
+extern crate rouille;
+
+mod http_api;
+
+fn main() {
+    println!("Starting server on port 8000");
+    http_api::start_server();
+}
@@ -0,0 +1,306 @@
+use crate::geom::Point;
+use crate::io::{Task, MUSICIAN_RADIUS};
+use crate::score::{attendee_score_without_q, calc_points_visibility};
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+pub struct CandidateParams {
+    hex_offsets: usize,
+    max_hex_candidates: usize,
+    border_rows: usize,
+    pillar_ring_points: usize,
+    max_attendee_facing: usize,
+}
+
+impl Default for CandidateParams {
+    fn default() -> Self {
+        CandidateParams {
+            hex_offsets: 3,
+            max_hex_candidates: 4000,
+            border_rows: 2,
+            pillar_ring_points: 24,
+            max_attendee_facing: 1000,
+        }
+    }
+}
+
+pub struct Candidates {
+    pub positions: Vec<Point>,
+    // score by instrument, then by candidate, only pillars are considered as blockers
+    pub scores: Vec<Vec<i64>>,
+}
+
+impl Candidates {
+    pub fn for_task(task: &Task) -> Self {
+        Self::with_params(task, &Default::default())
+    }
+
+    pub fn with_params(task: &Task, params: &CandidateParams) -> Self {
+        let positions = generate_candidates(task, params);
+        let scores = score_candidates(task, &positions, &[]);
+        Candidates { positions, scores }
+    }
+
+    // Candidate indices sorted by score for the instrument, best first
+    pub fn best_for_instrument(&self, instrument: usize) -> Vec<usize> {
+        let mut indices = (0..self.positions.len()).collect::<Vec<_>>();
+        indices.sort_by_key(|idx| -self.scores[instrument][*idx]);
+        indices
+    }
+}
+
+fn stage_inner_bounds(task: &Task) -> (f64, f64, f64, f64) {
+    (
+        task.stage_left() + MUSICIAN_RADIUS,
+        task.stage_right() - MUSICIAN_RADIUS,
+        task.stage_bottom() + MUSICIAN_RADIUS,
+        task.stage_top() - MUSICIAN_RADIUS,
+    )
+}
+
+fn hex_packing(task: &Task, spacing: f64, offset: (f64, f64)) -> Vec<Point> {
+    let (left, right, bottom, top) = stage_inner_bounds(task);
+    let row_step = spacing * 60.0f64.to_radians().sin();
+
+    let mut res = vec![];
+    let mut y = bottom + offset.1;
+    let mut even = false;
+    while y <= top {
+        let mut x = left + offset.0 + if even { spacing / 2.0 } else { 0.0 };
+        while x <= right {
+            res.push(Point { x, y });
+            x += spacing;
+        }
+        y += row_step;
+        even = !even;
+    }
+    res
+}
+
+fn border_rows(task: &Task, rows: usize) -> Vec<Point> {
+    let (left, right, bottom, top) = stage_inner_bounds(task);
+    let row_step = MUSICIAN_RADIUS * 60.0f64.to_radians().sin();
+
+    let mut res = vec![];
+    for row in 0..rows {
+        let inset = row as f64 * row_step;
+        let shift = if row % 2 == 1 {
+            MUSICIAN_RADIUS / 2.0
+        } else {
+            0.0
+        };
+
+        let mut x = left + shift;
+        while x <= right {
+            res.push(Point {
+                x,
+                y: bottom + inset,
+            });
+            res.push(Point { x, y: top - inset });
+            x += MUSICIAN_RADIUS;
+        }
+        let mut y = bottom + shift;
+        while y <= top {
+            res.push(Point { x: left + inset, y });
+            res.push(Point {
+                x: right - inset,
+                y,
+            });
+            y += MUSICIAN_RADIUS;
+        }
+    }
+    res
+}
+
+fn pillar_rings(task: &Task, points: usize) -> Vec<Point> {
+    task.pillars
+        .iter()
+        .flat_map(|pillar| {
+            let ring_radius = pillar.radius + MUSICIAN_RADIUS;
+            (0..points).map(move |i| {
+                let angle = std::f64::consts::TAU * i as f64 / points as f64;
+                Point {
+                    x: pillar.center.0 + ring_radius * angle.cos(),
+                    y: pillar.center.1 + ring_radius * angle.sin(),
+                }
+            })
+        })
+        .collect()
+}
+
+// Closest stage point for every attendee, this is where attendee hears musician the best
+fn attendee_facing(task: &Task, max_points: usize) -> Vec<Point> {
+    let (left, right, bottom, top) = stage_inner_bounds(task);
+    if left > right || bottom > top {
+        // no musician fits the stage
+        return vec![];
+    }
+    let step = (task.attendees.len() / max_points.max(1)).max(1);
+    task.attendees
+        .iter()
+        .step_by(step)
+        .map(|att| Point {
+            x: att.x.clamp(left, right),
+            y: att.y.clamp(bottom, top),
+        })
+        .collect()
+}
+
+pub fn generate_candidates(task: &Task, params: &CandidateParams) -> Vec<Point> {
+    let (left, right, bottom, top) = stage_inner_bounds(task);
+    let area = (right - left).max(0.0) * (top - bottom).max(0.0);
+    let hex_cell_area = MUSICIAN_RADIUS * MUSICIAN_RADIUS * 60.0f64.to_radians().sin();
+    let hex_per_offset = params.max_hex_candidates / params.hex_offsets.max(1);
+    let spacing = MUSICIAN_RADIUS
+        * (area / hex_cell_area / hex_per_offset as f64)
+            .sqrt()
+            .max(1.0);
+
+    let mut candidates = vec![];
+    for i in 0..params.hex_offsets {
+        let shift = i as f64 / params.hex_offsets as f64;
+        candidates.extend(hex_packing(
+            task,
+            spacing,
+            (shift * spacing, shift * spacing / 2.0),
+        ));
+    }
+    candidates.extend(border_rows(task, params.border_rows));
+    candidates.extend(pillar_rings(task, params.pillar_ring_points));
+    candidates.extend(attendee_facing(task, params.max_attendee_facing));
+
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|p| task.musician_in_stage(p.x, p.y))
+        .filter(|p| {
+            task.pillars
+                .iter()
+                .all(|pillar| p.dist(pillar.point()) > pillar.radius)
+        })
+        .filter(|p| seen.insert(((p.x * 100.0).round() as i64, (p.y * 100.0).round() as i64)))
+        .collect()
+}
+
+// Score of every instrument at every position, blockers are musicians which stay in place
+pub fn score_candidates(task: &Task, positions: &[Point], blockers: &[Point]) -> Vec<Vec<i64>> {
+    let visibility = calc_points_visibility(task, blockers, positions);
+    let by_candidate = positions
+        .par_iter()
+        .zip(visibility.par_iter())
+        .map(|(pos, visible)| {
+            (0..task.instruments_len())
+                .map(|instrument| {
+                    task.attendees
+                        .iter()
+                        .enumerate()
+                        .filter(|(att_idx, _)| visible[*att_idx])
+                        .map(|(_, att)| attendee_score_without_q(att, instrument, *pos))
+                        .sum::<i64>()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    (0..task.instruments_len())
+        .map(|instrument| by_candidate.iter().map(|s| s[instrument]).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_candidates, score_candidates, Candidates};
+    use crate::geom::Point;
+    use crate::io::{Attendee, Pillar, Task};
+
+    fn task(stage_width: f64, stage_height: f64) -> Task {
+        Task {
+            room_width: 1000.0,
+            room_height: 1000.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width,
+            stage_height,
+            stage_bottom_left: (300.0, 300.0),
+            musicians: vec![0, 1],
+            attendees: vec![
+                Attendee {
+                    x: 100.0,
+                    y: 400.0,
+                    tastes: vec![100.0, -100.0],
+                },
+                Attendee {
+                    x: 900.0,
+                    y: 400.0,
+                    tastes: vec![-100.0, 100.0],
+                },
+            ],
+            pillars: vec![Pillar {
+                center: (400.0, 400.0),
+                radius: 20.0,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_candidates_are_on_stage_and_outside_pillars() {
+        let task = task(200.0, 200.0);
+        let positions = generate_candidates(&task, &Default::default());
+        assert!(!positions.is_empty());
+        for p in &positions {
+            assert!(task.musician_in_stage(p.x, p.y), "{p:?}");
+            assert!(
+                p.dist(task.pillars[0].point()) > task.pillars[0].radius,
+                "{p:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_candidates_on_narrow_stage() {
+        // narrower than a musician, nothing fits but nothing panics either
+        let candidates = Candidates::for_task(&task(15.0, 200.0));
+        assert!(candidates.positions.is_empty());
+        assert!(candidates.scores.iter().all(|s| s.is_empty()));
+
+        // single column exactly
+        let candidates = Candidates::for_task(&task(20.0, 200.0));
+        assert!(!candidates.positions.is_empty());
+        assert!(candidates
+            .positions
+            .iter()
+            .all(|p| (p.x - 310.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_best_for_instrument_prefers_side_of_fans() {
+        let task = task(200.0, 200.0);
+        let candidates = Candidates::for_task(&task);
+        for instrument in 0..2 {
+            let best = candidates.best_for_instrument(instrument);
+            let scores = best
+                .iter()
+                .map(|idx| candidates.scores[instrument][*idx])
+                .collect::<Vec<_>>();
+            assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+        }
+        // instrument 0 is liked by the left attendee, instrument 1 by the right one
+        let best_left = candidates.positions[candidates.best_for_instrument(0)[0]];
+        let best_right = candidates.positions[candidates.best_for_instrument(1)[0]];
+        assert!(best_left.x < best_right.x);
+    }
+
+    #[test]
+    fn test_blockers_hide_attendees() {
+        let task = Task {
+            pillars: vec![],
+            ..task(200.0, 200.0)
+        };
+        let position = Point { x: 320.0, y: 400.0 };
+        let free = score_candidates(&task, &[position], &[]);
+        assert!(free[0][0] > 0);
+        // musician right between the candidate and the left attendee, only the right one who
+        // dislikes instrument 0 hears it now
+        let blocked = score_candidates(&task, &[position], &[Point { x: 310.0, y: 400.0 }]);
+        assert!(blocked[0][0] < 0);
+    }
+}
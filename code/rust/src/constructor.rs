@@ -0,0 +1,240 @@
+use crate::geom::Point;
+use crate::io::{Solution, Task, MUSICIAN_RADIUS};
+use crate::score;
+use crate::solution;
+use anyhow::{bail, Result};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::Serialize;
+
+const SPREADS: [f64; 10] = [5.0, 3.0, 2.0, 1.5, 1.1, 1.05, 1.01, 1.005, 1.001, 1.0];
+const RANDOM_PLACEMENT_TRIES: usize = 10_000;
+const RANDOM_SEED: u64 = 42;
+
+// Layouts which build a solution from scratch, usable as a base for optimizers
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Constructor {
+    // square grid from bottom left corner
+    Grid,
+    // dense hex packing scaled to the stage height
+    Hex,
+    // hex packing with the biggest gaps which fit
+    Spread,
+    // best of many hex spreads, improved by greedy placement
+    MultiSpread,
+    // zigzag for stages narrower than two musicians
+    Narrow,
+    // uniformly random positions
+    RandomValid,
+    // stage perimeter nearest to attendees first, then inner rings
+    BorderFirst,
+}
+
+impl Constructor {
+    pub const ALL: [Constructor; 7] = [
+        Constructor::Grid,
+        Constructor::Hex,
+        Constructor::Spread,
+        Constructor::MultiSpread,
+        Constructor::Narrow,
+        Constructor::RandomValid,
+        Constructor::BorderFirst,
+    ];
+
+    // Order in which constructors are tried when the requested one does not fit
+    const FALLBACK: [Constructor; 6] = [
+        Constructor::BorderFirst,
+        Constructor::Spread,
+        Constructor::Hex,
+        Constructor::Grid,
+        Constructor::Narrow,
+        Constructor::RandomValid,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Constructor::Grid => "grid",
+            Constructor::Hex => "hex",
+            Constructor::Spread => "spread",
+            Constructor::MultiSpread => "multi-spread",
+            Constructor::Narrow => "narrow",
+            Constructor::RandomValid => "random",
+            Constructor::BorderFirst => "border",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Constructor> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    // Returns error if layout does not fit the stage
+    pub fn build(self, task: &Task) -> Result<Solution> {
+        let solution = match self {
+            Constructor::Grid => solution::dummy(task),
+            Constructor::Hex => solution::dummy_hex(task, 1.0, true),
+            Constructor::Spread => spread(task)?,
+            Constructor::MultiSpread => solution::multi_dummy_solver(task)?,
+            Constructor::Narrow => {
+                let short_side = task.stage_width.min(task.stage_height);
+                if short_side >= 4.0 * MUSICIAN_RADIUS {
+                    bail!("Stage is too wide for narrow layout");
+                }
+                solution::canonical_solver(solution::dummy_narrow)(task)
+            }
+            Constructor::RandomValid => random_valid(task)?,
+            Constructor::BorderFirst => solution::border_first(task)?,
+        };
+        score::validate(task, &solution)?;
+        Ok(solution)
+    }
+}
+
+// Tries constructors in order, then the rest of them, returns the first one which fits the stage
+pub fn build_with_fallback(
+    task: &Task,
+    preferred: &[Constructor],
+) -> Result<(Constructor, Solution)> {
+    let fallback = Constructor::FALLBACK
+        .into_iter()
+        .filter(|c| !preferred.contains(c));
+    for constructor in preferred.iter().copied().chain(fallback) {
+        match constructor.build(task) {
+            Ok(solution) => return Ok((constructor, solution)),
+            Err(err) => println!("Constructor {} failed: {err}", constructor.name()),
+        }
+    }
+    bail!("No constructor produced valid solution")
+}
+
+fn spread(task: &Task) -> Result<Solution> {
+    SPREADS
+        .into_iter()
+        .map(|spread| solution::dummy_hex(task, spread, true))
+        .find(|solution| score::validate(task, solution).is_ok())
+        .ok_or_else(|| anyhow::anyhow!("No spread of hex layout fits the stage"))
+}
+
+fn random_valid(task: &Task) -> Result<Solution> {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(RANDOM_SEED);
+    let mut res = Solution::default_for_task(task);
+    let (left, right) = (
+        task.stage_left() + MUSICIAN_RADIUS,
+        task.stage_right() - MUSICIAN_RADIUS,
+    );
+    let (bottom, top) = (
+        task.stage_bottom() + MUSICIAN_RADIUS,
+        task.stage_top() - MUSICIAN_RADIUS,
+    );
+    if left > right || bottom > top {
+        bail!("Stage is too small for any musician");
+    }
+    for mus_idx in 0..task.musicians.len() {
+        let p = (0..RANDOM_PLACEMENT_TRIES)
+            .map(|_| Point {
+                x: rng.gen_range(left..=right),
+                y: rng.gen_range(bottom..=top),
+            })
+            .find(|p| {
+                res.placements
+                    .iter()
+                    .all(|other| other.dist(*p) >= MUSICIAN_RADIUS)
+            });
+        match p {
+            Some(p) => res.placements.push(p),
+            None => bail!("Could not find random place for musician {mus_idx}"),
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_with_fallback, Constructor};
+    use crate::io::{Attendee, Task, MUSICIAN_RADIUS};
+
+    fn task(stage_width: f64, stage_height: f64, musicians: usize) -> Task {
+        Task {
+            room_width: 1000.0,
+            room_height: 1000.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width,
+            stage_height,
+            stage_bottom_left: (100.0, 100.0),
+            musicians: vec![0; musicians],
+            attendees: vec![Attendee {
+                x: 900.0,
+                y: 900.0,
+                tastes: vec![100.0],
+            }],
+            pillars: vec![],
+        }
+    }
+
+    #[test]
+    fn test_all_constructors_on_roomy_stage() {
+        let task = task(300.0, 200.0, 20);
+        for constructor in Constructor::ALL {
+            let res = constructor.build(&task);
+            if constructor == Constructor::Narrow {
+                assert!(res.is_err());
+            } else {
+                assert!(res.is_ok(), "{} failed", constructor.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_fallback_on_wide_stage() {
+        let wide = task(300.0, 200.0, 20);
+        let (constructor, _) = build_with_fallback(&wide, &[Constructor::Narrow]).unwrap();
+        assert_eq!(constructor, Constructor::BorderFirst);
+
+        // stage fits single zigzag column only
+        let narrow = task(30.0, 400.0, 20);
+        let (constructor, _) = build_with_fallback(&narrow, &[Constructor::Narrow]).unwrap();
+        assert_eq!(constructor, Constructor::Narrow);
+    }
+
+    #[test]
+    fn test_border_first_uses_edges_near_attendees() {
+        // the only attendee is above and to the right of the stage
+        let task = task(300.0, 200.0, 5);
+        let solution = Constructor::BorderFirst.build(&task).unwrap();
+        for p in solution.placements {
+            let on_top = (p.y - (task.stage_top() - MUSICIAN_RADIUS)).abs() < 1e-6;
+            let on_right = (p.x - (task.stage_right() - MUSICIAN_RADIUS)).abs() < 1e-6;
+            assert!(on_top || on_right, "{p:?}");
+        }
+    }
+
+    #[test]
+    fn test_random_valid_uses_minimal_distance() {
+        // only two musicians fit a diameter apart, but four fit a radius apart
+        let task = task(50.0, 30.0, 3);
+        assert!(Constructor::RandomValid.build(&task).is_ok());
+    }
+
+    #[test]
+    fn test_border_first_fills_crowded_stage() {
+        for (width, height) in [(100.0, 80.0), (60.0, 200.0), (33.0, 47.0)] {
+            let capacity = |constructor: Constructor| {
+                (1..1000)
+                    .take_while(|n| constructor.build(&task(width, height, *n)).is_ok())
+                    .last()
+                    .unwrap_or(0)
+            };
+            let hex = capacity(Constructor::Hex);
+            let border = capacity(Constructor::BorderFirst);
+            assert!(
+                border >= hex,
+                "{width}x{height}: hex {hex}, border {border}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fallback_fails_on_overfull_stage() {
+        let task = task(40.0, 40.0, 20);
+        assert!(build_with_fallback(&task, &[Constructor::Hex]).is_err());
+    }
+}
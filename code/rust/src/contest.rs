@@ -0,0 +1,334 @@
+use crate::io::Task;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.icfpcontest.com";
+const TOO_MANY_REQUESTS: u16 = 429;
+
+pub struct ContestClient {
+    base_url: String,
+    token: Option<String>,
+    agent: ureq::Agent,
+}
+
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+impl ContestClient {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        ContestClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(60))
+                .build(),
+        }
+    }
+
+    fn send(request: std::result::Result<ureq::Response, ureq::Error>) -> Result<Response> {
+        let response = match request {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(ureq::Error::Transport(err)) => bail!("Request failed: {err}"),
+        };
+        let status = response.status();
+        let body = response.into_string()?;
+        Ok(Response { status, body })
+    }
+
+    pub fn submit(&self, problem: usize, contents: &str) -> Result<Response> {
+        let body = serde_json::json!({
+            "problem_id": problem,
+            "contents": contents,
+        });
+        let mut request = self
+            .agent
+            .post(&format!("{}/submission", self.base_url))
+            .set("content-type", "application/json");
+        if let Some(token) = &self.token {
+            request = request.set("authorization", &format!("Bearer {token}"));
+        }
+        Self::send(request.send_string(&body.to_string()))
+    }
+
+    pub fn problem(&self, problem: usize) -> Result<Response> {
+        Self::send(
+            self.agent
+                .get(&format!("{}/problem", self.base_url))
+                .query("problem_id", &problem.to_string())
+                .call(),
+        )
+    }
+}
+
+// Downloads task and checks it, returns raw task json as served by api
+pub fn fetch_problem(client: &ContestClient, problem: usize) -> Result<String> {
+    #[derive(Deserialize)]
+    enum ProblemResponse {
+        Success(String),
+        Failure(String),
+    }
+
+    let response = client.problem(problem)?;
+    let data = match serde_json::from_str(&response.body) {
+        Ok(ProblemResponse::Success(data)) => data,
+        Ok(ProblemResponse::Failure(err)) => bail!("Api returned failure: {err}"),
+        Err(_) => bail!("Unexpected response {}: {}", response.status, response.body),
+    };
+    let task: Task = serde_json::from_str(&data).context("Task does not match schema")?;
+    task.check()?;
+    Ok(data)
+}
+
+// Files in data dir differ from api responses in formatting, e.g. trailing newline
+pub fn same_task(current: &str, fetched: &str) -> bool {
+    match (
+        serde_json::from_str::<Task>(current),
+        serde_json::from_str::<Task>(fetched),
+    ) {
+        (Ok(current), Ok(fetched)) => current == fetched,
+        _ => false,
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubmissionLogEntry {
+    pub problem: usize,
+    // unix time in seconds
+    pub timestamp: u64,
+    pub solution_hash: String,
+    // score of the solution by our scorer
+    pub score: Option<i64>,
+    // None if request did not reach the server
+    pub status: Option<u16>,
+    pub response: String,
+}
+
+impl SubmissionLogEntry {
+    pub fn is_success(&self) -> bool {
+        self.status.is_some_and(|s| (200..300).contains(&s))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueuedSubmission {
+    pub problem: usize,
+    pub contents: String,
+    pub score: Option<i64>,
+}
+
+pub struct SubmitParams {
+    // minimal time between two requests
+    pub interval: Duration,
+    pub retries: usize,
+}
+
+impl Default for SubmitParams {
+    fn default() -> Self {
+        SubmitParams {
+            interval: Duration::from_secs(1),
+            retries: 3,
+        }
+    }
+}
+
+// FNV-1a, stable between runs and compiler versions unlike DefaultHasher
+pub fn solution_hash(contents: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in contents.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{hash:016x}")
+}
+
+pub fn read_log(path: &str) -> Vec<SubmissionLogEntry> {
+    std::fs::read_to_string(path)
+        .map(|data| {
+            data.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn append_log(path: &str, entry: &SubmissionLogEntry) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+pub fn read_queue(path: &str) -> Vec<QueuedSubmission> {
+    std::fs::read_to_string(path)
+        .map(|data| {
+            data.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Writes to temp file first, so that interrupted run does not leave half of the queue
+pub fn write_queue(path: &str, queue: &[QueuedSubmission]) -> Result<()> {
+    let tmp_path = format!("{path}.tmp");
+    let mut data = String::new();
+    for item in queue {
+        data += &serde_json::to_string(item)?;
+        data += "\n";
+    }
+    std::fs::write(&tmp_path, data)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+// Adds solutions to the queue on disk, solutions which are already queued are skipped
+pub fn enqueue(path: &str, items: Vec<QueuedSubmission>) -> Result<()> {
+    let mut queue = read_queue(path);
+    for item in items {
+        let hash = solution_hash(&item.contents);
+        let queued = queue
+            .iter()
+            .any(|q| q.problem == item.problem && solution_hash(&q.contents) == hash);
+        if !queued {
+            queue.push(item);
+        }
+    }
+    write_queue(path, &queue)
+}
+
+// Network errors, rate limits and server errors may pass on the next run
+fn should_retry_later(entry: &SubmissionLogEntry) -> bool {
+    match entry.status {
+        None => true,
+        Some(status) => status == TOO_MANY_REQUESTS || status >= 500,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Sends solutions from the queue on disk one by one. Solutions which were already accepted
+// according to the log are skipped, every attempt is appended to the log. The queue file is
+// updated after every request and keeps only solutions worth retrying, so that submit can be
+// restarted. Returns entries of this run.
+pub fn process_queue(
+    client: &ContestClient,
+    queue_path: &str,
+    log_path: &str,
+    params: &SubmitParams,
+) -> Vec<SubmissionLogEntry> {
+    let mut submitted = read_log(log_path)
+        .into_iter()
+        .filter(|entry| entry.is_success())
+        .map(|entry| (entry.problem, entry.solution_hash))
+        .collect::<std::collections::HashSet<_>>();
+
+    let mut pending = read_queue(queue_path);
+    pending.reverse();
+    let mut deferred = vec![];
+    let save_queue = |deferred: &[QueuedSubmission], pending: &[QueuedSubmission]| {
+        let queue = deferred
+            .iter()
+            .chain(pending.iter().rev())
+            .cloned()
+            .collect::<Vec<_>>();
+        if let Err(err) = write_queue(queue_path, &queue) {
+            println!("Could not write submission queue: {err}");
+        }
+    };
+
+    let mut last_request: Option<Instant> = None;
+    let mut results = vec![];
+    while let Some(item) = pending.pop() {
+        let hash = solution_hash(&item.contents);
+        if submitted.contains(&(item.problem, hash.clone())) {
+            println!("Solution for task {} was already submitted", item.problem);
+            save_queue(&deferred, &pending);
+            continue;
+        }
+
+        let mut attempt = 0;
+        let entry = loop {
+            let wait = params.interval * 2u32.pow(attempt as u32);
+            if let Some(last) = last_request {
+                if last.elapsed() < wait {
+                    sleep(wait - last.elapsed());
+                }
+            }
+            last_request = Some(Instant::now());
+
+            let (status, response) = match client.submit(item.problem, &item.contents) {
+                Ok(response) => (Some(response.status), response.body),
+                Err(err) => (None, err.to_string()),
+            };
+            attempt += 1;
+            if status == Some(TOO_MANY_REQUESTS) && attempt <= params.retries {
+                println!("Rate limited on task {}, retrying", item.problem);
+                continue;
+            }
+            break SubmissionLogEntry {
+                problem: item.problem,
+                timestamp: now(),
+                solution_hash: hash.clone(),
+                score: item.score,
+                status,
+                response,
+            };
+        };
+
+        match entry.status {
+            Some(status) => println!(
+                "Submitted task {}: {status} {}",
+                item.problem,
+                entry.response.trim()
+            ),
+            None => println!("Could not submit task {}: {}", item.problem, entry.response),
+        }
+        if let Err(err) = append_log(log_path, &entry) {
+            println!("Could not write submission log: {err}");
+        }
+        if entry.is_success() {
+            submitted.insert((item.problem, hash));
+        }
+        if should_retry_later(&entry) {
+            deferred.push(item);
+        }
+        save_queue(&deferred, &pending);
+        results.push(entry);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::same_task;
+
+    const TASK: &str = r#"{"room_width":100.0,"room_height":100.0,"stage_width":20.0,"stage_height":20.0,"stage_bottom_left":[10.0,10.0],"musicians":[0],"attendees":[{"x":50.0,"y":50.0,"tastes":[1.0]}],"pillars":[]}"#;
+
+    #[test]
+    fn test_same_task_ignores_formatting() {
+        assert!(same_task(&format!("{TASK}\n"), TASK));
+        let pretty =
+            serde_json::to_string_pretty(&serde_json::from_str::<serde_json::Value>(TASK).unwrap())
+                .unwrap();
+        assert!(same_task(&pretty, TASK));
+        assert!(!same_task(TASK, &TASK.replace("\"x\":50.0", "\"x\":51.0")));
+        assert!(!same_task("", TASK));
+    }
+}
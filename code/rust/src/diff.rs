@@ -0,0 +1,190 @@
+use crate::geom::Point;
+use crate::io::{Solution, Task};
+use crate::score::{calc_ex, calc_visibility_fast, validate};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+const MOVE_EPS: f64 = 1e-9;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MusicianMove {
+    pub musician: usize,
+    pub from: Point,
+    pub to: Point,
+    pub distance: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct VolumeChange {
+    pub musician: usize,
+    pub before: f64,
+    pub after: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SolutionDiff {
+    pub score_before: i64,
+    pub score_after: i64,
+    pub moves: Vec<MusicianMove>,
+    pub volume_changes: Vec<VolumeChange>,
+    // after minus before, by musician and by attendee
+    pub musician_delta: Vec<i64>,
+    pub attendee_delta: Vec<i64>,
+    // (attendee, musician) pairs
+    pub newly_blocked: Vec<(usize, usize)>,
+    pub newly_unblocked: Vec<(usize, usize)>,
+}
+
+pub fn diff(task: &Task, before: &Solution, after: &Solution) -> Result<SolutionDiff> {
+    if before.placements.len() != after.placements.len() {
+        bail!(
+            "Solutions have different number of musicians: {} and {}",
+            before.placements.len(),
+            after.placements.len()
+        );
+    }
+    validate(task, before).context("First solution is incorrect")?;
+    validate(task, after).context("Second solution is incorrect")?;
+
+    let moves = before
+        .placements
+        .iter()
+        .zip(after.placements.iter())
+        .enumerate()
+        .filter(|(_, (from, to))| from.dist(**to) > MOVE_EPS)
+        .map(|(musician, (from, to))| MusicianMove {
+            musician,
+            from: *from,
+            to: *to,
+            distance: from.dist(*to),
+        })
+        .collect();
+    let volume_changes = before
+        .volumes
+        .iter()
+        .zip(after.volumes.iter())
+        .enumerate()
+        .filter(|(_, (b, a))| b != a)
+        .map(|(musician, (b, a))| VolumeChange {
+            musician,
+            before: *b,
+            after: *a,
+        })
+        .collect();
+
+    let visibility_before = calc_visibility_fast(task, before);
+    let visibility_after = calc_visibility_fast(task, after);
+    let score_before = calc_ex(task, before, &visibility_before);
+    let score_after = calc_ex(task, after, &visibility_after);
+
+    let mut newly_blocked = vec![];
+    let mut newly_unblocked = vec![];
+    for att_idx in 0..task.attendees.len() {
+        for mus_idx in 0..task.musicians.len() {
+            match (
+                visibility_before.is_visible(att_idx, mus_idx),
+                visibility_after.is_visible(att_idx, mus_idx),
+            ) {
+                (true, false) => newly_blocked.push((att_idx, mus_idx)),
+                (false, true) => newly_unblocked.push((att_idx, mus_idx)),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(SolutionDiff {
+        score_before: score_before.score,
+        score_after: score_after.score,
+        moves,
+        volume_changes,
+        musician_delta: score_after
+            .musician
+            .iter()
+            .zip(score_before.musician.iter())
+            .map(|(a, b)| a - b)
+            .collect(),
+        attendee_delta: score_after
+            .attendee
+            .iter()
+            .zip(score_before.attendee.iter())
+            .map(|(a, b)| a - b)
+            .collect(),
+        newly_blocked,
+        newly_unblocked,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff;
+    use crate::geom::Point;
+    use crate::io::{Attendee, Solution, Task};
+
+    fn task() -> Task {
+        Task {
+            room_width: 400.0,
+            room_height: 400.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 100.0,
+            stage_height: 100.0,
+            stage_bottom_left: (150.0, 150.0),
+            musicians: vec![0, 1, 0],
+            attendees: vec![
+                Attendee {
+                    x: 100.0,
+                    y: 200.0,
+                    tastes: vec![1000.0, -500.0],
+                },
+                Attendee {
+                    x: 300.0,
+                    y: 380.0,
+                    tastes: vec![200.0, 800.0],
+                },
+            ],
+            pillars: vec![],
+        }
+    }
+
+    fn solution() -> Solution {
+        Solution {
+            placements: vec![
+                Point { x: 170.0, y: 200.0 },
+                Point { x: 200.0, y: 230.0 },
+                Point { x: 230.0, y: 200.0 },
+            ],
+            volumes: vec![1.0; 3],
+        }
+    }
+
+    #[test]
+    fn test_diff_with_itself_is_empty() {
+        let res = diff(&task(), &solution(), &solution()).unwrap();
+        assert_eq!(res.score_before, res.score_after);
+        assert!(res.moves.is_empty());
+        assert!(res.volume_changes.is_empty());
+        assert!(res.musician_delta.iter().all(|d| *d == 0));
+        assert!(res.attendee_delta.iter().all(|d| *d == 0));
+        assert!(res.newly_blocked.is_empty());
+        assert!(res.newly_unblocked.is_empty());
+    }
+
+    #[test]
+    fn test_diff_single_move() {
+        let mut after = solution();
+        after.placements[1] = Point { x: 200.0, y: 240.0 };
+        let res = diff(&task(), &solution(), &after).unwrap();
+        assert_eq!(res.moves.len(), 1);
+        assert_eq!(res.moves[0].musician, 1);
+        assert!((res.moves[0].distance - 10.0).abs() < 1e-9);
+        assert_ne!(res.musician_delta[1], 0);
+        assert_eq!(
+            res.musician_delta.iter().sum::<i64>(),
+            res.score_after - res.score_before
+        );
+        assert_eq!(
+            res.attendee_delta.iter().sum::<i64>(),
+            res.score_after - res.score_before
+        );
+        assert_ne!(res.score_after, res.score_before);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::geom::Point;
+use crate::io::{default_volumes_task, Solution, Task, MUSICIAN_RADIUS};
+use crate::optimizer::legalize_placements;
+use crate::score::{calc, calc_visibility_fast, Visibility, MIN_SCORE};
+use crate::solution::recalc_volumes;
+use float_ord::FloatOrd;
+use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+// DE/rand/1 mutation needs target and three other individuals
+const MIN_POPULATION: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct EvolutionParams {
+    pub block_size: usize,
+    pub blocks: usize,
+    // raised to MIN_POPULATION if smaller
+    pub population: usize,
+    // total number of fitness evaluations is spread over all blocks and generations
+    pub max_evaluations: usize,
+    pub min_generations: usize,
+    // amount of musician x (musician + attendee) pairs we can afford to evaluate per call
+    pub pairs_budget: usize,
+    pub initial_spread: f64,
+    pub differential_weight: f64,
+    pub crossover_probability: f64,
+}
+
+impl Default for EvolutionParams {
+    fn default() -> Self {
+        EvolutionParams {
+            block_size: 6,
+            blocks: 3,
+            population: 12,
+            max_evaluations: 600,
+            min_generations: 1,
+            pairs_budget: 200_000_000,
+            initial_spread: 2.0 * MUSICIAN_RADIUS,
+            differential_weight: 0.7,
+            crossover_probability: 0.9,
+        }
+    }
+}
+
+struct Individual {
+    solution: Solution,
+    fitness: i64,
+}
+
+// Evaluates solution after repairing overlaps, so that every individual is a valid solution
+fn evaluate(task: &Task, mut solution: Solution) -> Individual {
+    if legalize_placements(task, &mut solution).is_err() {
+        return Individual {
+            solution,
+            fitness: MIN_SCORE,
+        };
+    }
+    solution.volumes = default_volumes_task(task);
+    let visibility = calc_visibility_fast(task, &solution);
+    recalc_volumes(task, &mut solution, &visibility);
+    let fitness = calc(task, &solution, &visibility).unwrap_or(MIN_SCORE);
+    Individual { solution, fitness }
+}
+
+// Seed musician and its closest neighbours
+fn pick_block(solution: &Solution, block_size: usize, rng: &mut Xoshiro256PlusPlus) -> Vec<usize> {
+    let seed = rng.gen_range(0..solution.placements.len());
+    let seed_pos = solution.placements[seed];
+    let mut indices = (0..solution.placements.len()).collect::<Vec<_>>();
+    indices.sort_by_key(|idx| FloatOrd(solution.placements[*idx].dist_sqr(seed_pos)));
+    indices.truncate(block_size);
+    indices
+}
+
+fn optimize_block(
+    task: &Task,
+    best: Individual,
+    block: &[usize],
+    generations: usize,
+    params: &EvolutionParams,
+    rng: &mut Xoshiro256PlusPlus,
+) -> Individual {
+    let spread = Uniform::from(-params.initial_spread..params.initial_spread);
+
+    let mut population = vec![];
+    for _ in 1..params.population.max(MIN_POPULATION) {
+        let mut solution = best.solution.clone();
+        for &idx in block {
+            let p = solution.placements[idx];
+            solution.placements[idx] = Point {
+                x: p.x + spread.sample(rng),
+                y: p.y + spread.sample(rng),
+            };
+        }
+        population.push(evaluate(task, solution));
+    }
+    population.push(best);
+
+    // DE/rand/1/bin over coordinates of block musicians
+    for _ in 0..generations {
+        for target in 0..population.len() {
+            let mut others = rand::seq::index::sample(rng, population.len() - 1, 3)
+                .into_iter()
+                .map(|i| if i >= target { i + 1 } else { i });
+            let (a, b, c) = (
+                others.next().unwrap(),
+                others.next().unwrap(),
+                others.next().unwrap(),
+            );
+
+            let forced_coord = rng.gen_range(0..block.len() * 2);
+            let mut trial = population[target].solution.clone();
+            for (block_idx, &idx) in block.iter().enumerate() {
+                let pa = population[a].solution.placements[idx];
+                let pb = population[b].solution.placements[idx];
+                let pc = population[c].solution.placements[idx];
+                let mutant = pa + (pb - pc) * params.differential_weight;
+
+                if forced_coord == block_idx * 2 || rng.gen_bool(params.crossover_probability) {
+                    trial.placements[idx].x = mutant.x;
+                }
+                if forced_coord == block_idx * 2 + 1 || rng.gen_bool(params.crossover_probability) {
+                    trial.placements[idx].y = mutant.y;
+                }
+            }
+
+            let trial = evaluate(task, trial);
+            if trial.fitness >= population[target].fitness {
+                population[target] = trial;
+            }
+        }
+    }
+
+    population
+        .into_iter()
+        .max_by_key(|individual| individual.fitness)
+        .unwrap()
+}
+
+pub fn differential_evolution(
+    task: &Task,
+    solution: &Solution,
+    visibility: &Visibility,
+    params: &EvolutionParams,
+    rng: &mut Xoshiro256PlusPlus,
+) -> (Solution, Visibility) {
+    if solution.placements.len() < 2 {
+        return (solution.clone(), visibility.clone());
+    }
+
+    let pairs = task.musicians.len() * (task.musicians.len() + task.attendees.len());
+    let evaluations = (params.pairs_budget / pairs.max(1)).min(params.max_evaluations);
+    let generations = (evaluations
+        / (params.blocks * params.population.max(MIN_POPULATION)).max(1))
+    .max(params.min_generations);
+
+    let mut best = evaluate(task, solution.clone());
+    for _ in 0..params.blocks {
+        let block = pick_block(&best.solution, params.block_size, rng);
+        best = optimize_block(task, best, &block, generations, params, rng);
+    }
+
+    let visibility = calc_visibility_fast(task, &best.solution);
+    (best.solution, visibility)
+}
+
+pub fn differential_evolution_optimizer(
+    task: &Task,
+    solution: &Solution,
+    visibility: &Visibility,
+    rng: &mut Xoshiro256PlusPlus,
+) -> (Solution, Visibility) {
+    differential_evolution(task, solution, visibility, &Default::default(), rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{differential_evolution, EvolutionParams};
+    use crate::io::{Attendee, Task};
+    use crate::score::{calc, calc_visibility_fast, validate};
+    use crate::solution::dummy;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    #[test]
+    fn test_differential_evolution_with_small_population() {
+        let task = Task {
+            room_width: 500.0,
+            room_height: 500.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 100.0,
+            stage_height: 100.0,
+            stage_bottom_left: (200.0, 200.0),
+            musicians: vec![0, 1, 0],
+            attendees: vec![Attendee {
+                x: 100.0,
+                y: 100.0,
+                tastes: vec![1000.0, -500.0],
+            }],
+            pillars: vec![],
+        };
+        let solution = dummy(&task);
+        let visibility = calc_visibility_fast(&task, &solution);
+        let before = calc(&task, &solution, &visibility).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        for population in [0, 1, 2, 3] {
+            let params = EvolutionParams {
+                population,
+                blocks: 1,
+                ..Default::default()
+            };
+            let (result, visibility) =
+                differential_evolution(&task, &solution, &visibility, &params, &mut rng);
+            assert!(validate(&task, &result).is_ok());
+            assert!(calc(&task, &result, &visibility).unwrap() >= before);
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use crate::geom::Point;
+use crate::io::{Attendee, Pillar, Task, MUSICIAN_RADIUS};
+use anyhow::{bail, ensure, Result};
+use rand::distributions::{Distribution, Uniform};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::str::FromStr;
+
+const MAX_PLACEMENT_TRIES: usize = 100_000;
+const CLUSTERS: usize = 5;
+const MIN_PILLAR_RADIUS: f64 = 5.0;
+const MAX_PILLAR_RADIUS: f64 = 80.0;
+
+#[derive(Debug, Clone, Copy)]
+pub enum AttendeeDistribution {
+    Uniform,
+    Clustered,
+    Ring,
+}
+
+impl FromStr for AttendeeDistribution {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "uniform" => Ok(Self::Uniform),
+            "clustered" => Ok(Self::Clustered),
+            "ring" => Ok(Self::Ring),
+            _ => bail!("Unknown attendee distribution {s}, expected uniform, clustered or ring"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TasteDistribution {
+    // every taste in [-1000, 1000)
+    Uniform,
+    // every taste in [0, 1000)
+    Positive,
+    // either strong like or strong dislike
+    Polarized,
+}
+
+impl FromStr for TasteDistribution {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "uniform" => Ok(Self::Uniform),
+            "positive" => Ok(Self::Positive),
+            "polarized" => Ok(Self::Polarized),
+            _ => bail!("Unknown taste distribution {s}, expected uniform, positive or polarized"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorParams {
+    pub room_width: f64,
+    pub room_height: f64,
+    pub stage_width: f64,
+    pub stage_height: f64,
+    pub musicians: usize,
+    pub instruments: usize,
+    pub attendees: usize,
+    pub attendee_distribution: AttendeeDistribution,
+    pub taste_distribution: TasteDistribution,
+    pub pillars: usize,
+    pub seed: u64,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        GeneratorParams {
+            room_width: 2000.0,
+            room_height: 2000.0,
+            stage_width: 500.0,
+            stage_height: 300.0,
+            musicians: 50,
+            instruments: 5,
+            attendees: 500,
+            attendee_distribution: AttendeeDistribution::Uniform,
+            taste_distribution: TasteDistribution::Uniform,
+            pillars: 0,
+            seed: 42,
+        }
+    }
+}
+
+// Musicians which surely fit the stage: grid layout places them a diameter apart
+fn stage_capacity(stage_width: f64, stage_height: f64) -> usize {
+    let step = 2.0 * MUSICIAN_RADIUS;
+    let columns = ((stage_width - 2.0 * MUSICIAN_RADIUS) / step).floor() + 1.0;
+    let rows = ((stage_height - 2.0 * MUSICIAN_RADIUS) / step).floor() + 1.0;
+    (columns.max(0.0) * rows.max(0.0)) as usize
+}
+
+fn on_stage(task: &Task, p: Point, margin: f64) -> bool {
+    p.x >= task.stage_left() - margin
+        && p.x <= task.stage_right() + margin
+        && p.y >= task.stage_bottom() - margin
+        && p.y <= task.stage_top() + margin
+}
+
+fn in_room(task: &Task, p: Point) -> bool {
+    p.x >= task.room_left()
+        && p.x <= task.room_right()
+        && p.y >= task.room_bottom()
+        && p.y <= task.room_top()
+}
+
+// Box-Muller transform, rand does not have normal distribution without rand_distr
+fn normal(rng: &mut impl Rng, sigma: f64) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen_range(0.0..1.0);
+    sigma * (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+fn taste(distribution: TasteDistribution, rng: &mut impl Rng) -> f64 {
+    match distribution {
+        TasteDistribution::Uniform => rng.gen_range(-1000.0..1000.0_f64).round(),
+        TasteDistribution::Positive => rng.gen_range(0.0..1000.0_f64).round(),
+        TasteDistribution::Polarized => {
+            let strength = rng.gen_range(500.0..1000.0_f64).round();
+            if rng.gen_bool(0.5) {
+                strength
+            } else {
+                -strength
+            }
+        }
+    }
+}
+
+fn sample_outside_stage(
+    task: &Task,
+    rng: &mut Xoshiro256PlusPlus,
+    margin: f64,
+    mut sample: impl FnMut(&mut Xoshiro256PlusPlus) -> Point,
+) -> Result<Point> {
+    for _ in 0..MAX_PLACEMENT_TRIES {
+        let p = sample(rng);
+        if in_room(task, p) && !on_stage(task, p, margin) {
+            return Ok(p);
+        }
+    }
+    bail!("Could not find free place in the room, try bigger room or smaller stage")
+}
+
+pub fn generate(params: &GeneratorParams) -> Result<Task> {
+    ensure!(
+        params.instruments > 0,
+        "At least one instrument is required"
+    );
+    ensure!(params.attendees > 0, "At least one attendee is required");
+    ensure!(params.musicians > 0, "At least one musician is required");
+    ensure!(
+        params.stage_width >= 2.0 * MUSICIAN_RADIUS && params.stage_height >= 2.0 * MUSICIAN_RADIUS,
+        "Stage should be at least {} in both directions",
+        2.0 * MUSICIAN_RADIUS
+    );
+    let capacity = stage_capacity(params.stage_width, params.stage_height);
+    ensure!(
+        params.musicians <= capacity,
+        "Only {capacity} musicians fit the stage, got {}",
+        params.musicians
+    );
+    ensure!(
+        params.stage_width < params.room_width && params.stage_height < params.room_height,
+        "Stage should be smaller than the room"
+    );
+
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(params.seed);
+
+    let stage_bottom_left = (
+        rng.gen_range(0.0..params.room_width - params.stage_width)
+            .round(),
+        rng.gen_range(0.0..params.room_height - params.stage_height)
+            .round(),
+    );
+
+    let mut task = Task {
+        room_width: params.room_width,
+        room_height: params.room_height,
+        room_bottom_left: (0.0, 0.0),
+        stage_width: params.stage_width,
+        stage_height: params.stage_height,
+        stage_bottom_left,
+        musicians: vec![],
+        attendees: vec![],
+        pillars: vec![],
+    };
+
+    // every instrument has at least one musician if possible
+    task.musicians = (0..params.musicians)
+        .map(|i| {
+            if i < params.instruments {
+                i
+            } else {
+                rng.gen_range(0..params.instruments)
+            }
+        })
+        .collect();
+
+    let stage_center = Point {
+        x: task.stage_left() + task.stage_width / 2.0,
+        y: task.stage_bottom() + task.stage_height / 2.0,
+    };
+    let x_distr = Uniform::from(0.0..params.room_width);
+    let y_distr = Uniform::from(0.0..params.room_height);
+    let clusters = (0..CLUSTERS)
+        .map(|_| {
+            sample_outside_stage(&task, &mut rng, MUSICIAN_RADIUS, |rng| Point {
+                x: x_distr.sample(rng),
+                y: y_distr.sample(rng),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let cluster_sigma = params.room_width.min(params.room_height) / 20.0;
+    let ring_inner = stage_center.dist(Point {
+        x: task.stage_left(),
+        y: task.stage_bottom(),
+    }) + MUSICIAN_RADIUS;
+    let ring_outer = ring_inner + params.room_width.min(params.room_height) / 10.0;
+
+    for _ in 0..params.attendees {
+        let coord = sample_outside_stage(&task, &mut rng, MUSICIAN_RADIUS, |rng| {
+            match params.attendee_distribution {
+                AttendeeDistribution::Uniform => Point {
+                    x: x_distr.sample(rng),
+                    y: y_distr.sample(rng),
+                },
+                AttendeeDistribution::Clustered => {
+                    let center = clusters[rng.gen_range(0..clusters.len())];
+                    Point {
+                        x: center.x + normal(rng, cluster_sigma),
+                        y: center.y + normal(rng, cluster_sigma),
+                    }
+                }
+                AttendeeDistribution::Ring => {
+                    let angle = rng.gen_range(0.0..std::f64::consts::TAU);
+                    let r = rng.gen_range(ring_inner..ring_outer);
+                    Point {
+                        x: stage_center.x + r * angle.cos(),
+                        y: stage_center.y + r * angle.sin(),
+                    }
+                }
+            }
+        })?;
+        let tastes = (0..params.instruments)
+            .map(|_| taste(params.taste_distribution, &mut rng))
+            .collect();
+        task.attendees.push(Attendee {
+            x: coord.x.round(),
+            y: coord.y.round(),
+            tastes,
+        });
+    }
+
+    for _ in 0..params.pillars {
+        let radius = rng.gen_range(MIN_PILLAR_RADIUS..MAX_PILLAR_RADIUS).round();
+        // attendee inside of pillar would make the task invalid
+        let mut center = None;
+        for _ in 0..MAX_PLACEMENT_TRIES {
+            let p = sample_outside_stage(&task, &mut rng, radius, |rng| Point {
+                x: x_distr.sample(rng).round(),
+                y: y_distr.sample(rng).round(),
+            })?;
+            if task.attendees.iter().all(|a| a.coord().dist(p) > radius) {
+                center = Some(p);
+                break;
+            }
+        }
+        let Some(center) = center else {
+            bail!(
+                "Could not find place for pillar without attendees, try fewer attendees or pillars"
+            );
+        };
+        task.pillars.push(Pillar {
+            center: (center.x, center.y),
+            radius,
+        });
+    }
+
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        generate, stage_capacity, AttendeeDistribution, GeneratorParams, TasteDistribution,
+    };
+    use crate::constructor::{build_with_fallback, Constructor};
+    use crate::score::validate;
+
+    fn params(seed: u64) -> GeneratorParams {
+        GeneratorParams {
+            room_width: 600.0,
+            room_height: 500.0,
+            stage_width: 150.0,
+            stage_height: 100.0,
+            musicians: 12,
+            instruments: 3,
+            attendees: 60,
+            pillars: 4,
+            seed,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_task() {
+        let a = serde_json::to_string(&generate(&params(7)).unwrap()).unwrap();
+        let b = serde_json::to_string(&generate(&params(7)).unwrap()).unwrap();
+        let c = serde_json::to_string(&generate(&params(8)).unwrap()).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_generated_tasks_are_solvable() {
+        let distributions = [
+            AttendeeDistribution::Uniform,
+            AttendeeDistribution::Clustered,
+            AttendeeDistribution::Ring,
+        ];
+        let tastes = [
+            TasteDistribution::Uniform,
+            TasteDistribution::Positive,
+            TasteDistribution::Polarized,
+        ];
+        for seed in 0..3 {
+            for attendee_distribution in distributions {
+                for taste_distribution in tastes {
+                    let task = generate(&GeneratorParams {
+                        attendee_distribution,
+                        taste_distribution,
+                        ..params(seed)
+                    })
+                    .unwrap();
+                    task.check().unwrap();
+                    for pillar in &task.pillars {
+                        for a in &task.attendees {
+                            assert!(a.coord().dist(pillar.point()) > pillar.radius);
+                        }
+                    }
+                    let (_, solution) = build_with_fallback(&task, &[Constructor::Grid]).unwrap();
+                    assert!(validate(&task, &solution).is_ok());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_full_stage_is_solvable() {
+        let capacity = stage_capacity(60.0, 40.0);
+        assert_eq!(capacity, 6);
+        let task = generate(&GeneratorParams {
+            stage_width: 60.0,
+            stage_height: 40.0,
+            musicians: capacity,
+            ..params(1)
+        })
+        .unwrap();
+        build_with_fallback(&task, &[]).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_unsolvable_params() {
+        let narrow = GeneratorParams {
+            stage_width: 15.0,
+            ..params(1)
+        };
+        assert!(generate(&narrow).is_err());
+        let crowded = GeneratorParams {
+            musicians: stage_capacity(150.0, 100.0) + 1,
+            ..params(1)
+        };
+        assert!(generate(&crowded).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::geom::Point;
 use crate::io::{default_volumes_task, Solution, Task};
-use crate::score::{calc, calc_visibility, Visibility};
+use crate::score::{calc, calc_visibility, Visibility, MIN_SCORE};
 use crate::solution::recalc_volumes;
 use genevo::{operator::prelude::*, population::*, prelude::*};
 
@@ -44,8 +44,6 @@ fn genome_to_solution(task: &Task, genome: &Genome) -> Solution {
     }
 }
 
-const MIN_SCORE: i64 = -1_000_000_000_000;
-
 /// The genotype is Solution
 impl<'a> FitnessFunction<Genome, i64> for &'a Task {
     fn fitness_of(&self, genome: &Genome) -> i64 {
@@ -2,7 +2,7 @@ use derive_more::{Add, Mul, Sum};
 use serde::{Deserialize, Serialize};
 use std::ops::Sub;
 
-#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -94,9 +94,141 @@ impl Segment {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipX,
+    FlipY,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipX,
+        Symmetry::FlipY,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    // Rows of rotation/reflection matrix
+    fn matrix(self) -> [[f64; 2]; 2] {
+        match self {
+            Symmetry::Identity => [[1.0, 0.0], [0.0, 1.0]],
+            Symmetry::Rotate90 => [[0.0, -1.0], [1.0, 0.0]],
+            Symmetry::Rotate180 => [[-1.0, 0.0], [0.0, -1.0]],
+            Symmetry::Rotate270 => [[0.0, 1.0], [-1.0, 0.0]],
+            Symmetry::FlipX => [[-1.0, 0.0], [0.0, 1.0]],
+            Symmetry::FlipY => [[1.0, 0.0], [0.0, -1.0]],
+            Symmetry::Transpose => [[0.0, 1.0], [1.0, 0.0]],
+            Symmetry::AntiTranspose => [[0.0, -1.0], [-1.0, 0.0]],
+        }
+    }
+}
+
+// p -> M * p + offset, where M is one of the room symmetries
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    matrix: [[f64; 2]; 2],
+    offset: Vector,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self::translation(Vector { x: 0.0, y: 0.0 })
+    }
+
+    pub fn translation(offset: Vector) -> Self {
+        Self {
+            matrix: Symmetry::Identity.matrix(),
+            offset,
+        }
+    }
+
+    // Symmetry of the box [0, width] x [0, height], result is again a box with corner in origin
+    pub fn for_box(symmetry: Symmetry, width: f64, height: f64) -> Self {
+        let rotation = Self {
+            matrix: symmetry.matrix(),
+            offset: Vector { x: 0.0, y: 0.0 },
+        };
+        let (corner, _, _) = rotation.rect(Point { x: 0.0, y: 0.0 }, width, height);
+        rotation.then(&Self::translation(Point::default() - corner))
+    }
+
+    pub fn apply(&self, p: Point) -> Point {
+        let [[xx, xy], [yx, yy]] = self.matrix;
+        Point {
+            x: xx * p.x + xy * p.y,
+            y: yx * p.x + yy * p.y,
+        } + self.offset
+    }
+
+    // Transform of axis-aligned rectangle, returns new bottom left corner, width and height
+    pub fn rect(&self, bottom_left: Point, width: f64, height: f64) -> (Point, f64, f64) {
+        let a = self.apply(bottom_left);
+        let b = self.apply(Point {
+            x: bottom_left.x + width,
+            y: bottom_left.y + height,
+        });
+        (
+            Point {
+                x: a.x.min(b.x),
+                y: a.y.min(b.y),
+            },
+            (a.x - b.x).abs(),
+            (a.y - b.y).abs(),
+        )
+    }
+
+    // Applies self, then other
+    pub fn then(&self, other: &Self) -> Self {
+        let [[a, b], [c, d]] = other.matrix;
+        let [[e, f], [g, h]] = self.matrix;
+        Self {
+            matrix: [
+                [a * e + b * g, a * f + b * h],
+                [c * e + d * g, c * f + d * h],
+            ],
+            offset: Vector {
+                x: a * self.offset.x + b * self.offset.y,
+                y: c * self.offset.x + d * self.offset.y,
+            } + other.offset,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        // matrix is orthogonal, so inverse is transposed matrix
+        let [[xx, xy], [yx, yy]] = self.matrix;
+        let matrix = [[xx, yx], [xy, yy]];
+        let rotated_offset = Self {
+            matrix,
+            offset: Vector { x: 0.0, y: 0.0 },
+        }
+        .apply(Point {
+            x: self.offset.x,
+            y: self.offset.y,
+        });
+        Self {
+            matrix,
+            offset: Vector {
+                x: -rotated_offset.x,
+                y: -rotated_offset.y,
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Point, Segment};
+    use super::{Point, Segment, Symmetry, Transform};
+    use proptest::prelude::*;
 
     fn assert_f64_eq(a: f64, b: f64) {
         println!("{}, {}", a, b);
@@ -112,4 +244,81 @@ mod tests {
 
         assert_f64_eq(seg.dist(Point { x: 2.0, y: 2.0 }), 0.0);
     }
+
+    #[test]
+    fn test_transform_inverse() {
+        let p = Point { x: 3.0, y: 7.0 };
+        for symmetry in Symmetry::ALL {
+            let t = Transform::for_box(symmetry, 10.0, 20.0);
+            let (corner, w, h) = t.rect(Point { x: 0.0, y: 0.0 }, 10.0, 20.0);
+            assert_f64_eq(corner.x, 0.0);
+            assert_f64_eq(corner.y, 0.0);
+            assert_f64_eq(w * h, 200.0);
+
+            let back = t.inverse().apply(t.apply(p));
+            assert_f64_eq(back.x, p.x);
+            assert_f64_eq(back.y, p.y);
+        }
+    }
+
+    fn point() -> impl Strategy<Value = Point> {
+        (-1000.0..1000.0, -1000.0..1000.0).prop_map(|(x, y)| Point { x, y })
+    }
+
+    fn segment() -> impl Strategy<Value = Segment> {
+        prop_oneof![
+            (point(), point()).prop_map(|(from, to)| Segment { from, to }),
+            // zero-length segment
+            point().prop_map(|p| Segment { from: p, to: p }),
+            // axis-aligned segments and points on the same line are typical for integer inputs
+            (-1000i32..1000, -1000i32..1000, -1000i32..1000).prop_map(|(x, y1, y2)| Segment {
+                from: Point {
+                    x: x as f64,
+                    y: y1 as f64
+                },
+                to: Point {
+                    x: x as f64,
+                    y: y2 as f64
+                },
+            }),
+        ]
+    }
+
+    // Minimal distance to points sampled along the segment
+    fn sampled_dist(seg: Segment, p: Point, samples: usize) -> f64 {
+        (0..=samples)
+            .map(|i| {
+                let t = i as f64 / samples as f64;
+                let on_segment = seg.from + (seg.to - seg.from) * t;
+                on_segment.dist(p)
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_segment_dist_matches_sampling(seg in segment(), p in point()) {
+            const SAMPLES: usize = 2000;
+            let dist = seg.dist(p);
+            let sampled = sampled_dist(seg, p, SAMPLES);
+            let step = seg.from.dist(seg.to) / SAMPLES as f64;
+            prop_assert!(dist <= sampled + 1e-9, "{dist} > {sampled}");
+            prop_assert!(dist >= sampled - step - 1e-9, "{dist} < {sampled}");
+        }
+
+        #[test]
+        fn prop_segment_dist_is_symmetric(seg in segment(), p in point()) {
+            let reversed = Segment {
+                from: seg.to,
+                to: seg.from,
+            };
+            prop_assert!((seg.dist(p) - reversed.dist(p)).abs() < 1e-6);
+        }
+
+        #[test]
+        fn prop_segment_dist_at_ends(seg in segment()) {
+            prop_assert!(seg.dist(seg.from) < 1e-9);
+            prop_assert!(seg.dist(seg.to) < 1e-9);
+        }
+    }
 }
@@ -0,0 +1,117 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const HISTORY_FILE: &str = "../../history.jsonl";
+
+// One improvement of the best known solution, stored as a line of json
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub problem: usize,
+    // unix time in seconds
+    pub timestamp: u64,
+    pub score: i64,
+    pub previous_score: Option<i64>,
+    // what produced the solution: "optimize dummy", "ortools", "manual", ...
+    pub source: String,
+    // optimizer chains which improved the score, in order
+    pub chains: Vec<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(problem: usize, score: i64, source: &str, chains: Vec<String>) -> Self {
+        HistoryEntry {
+            problem,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            score,
+            previous_score: None,
+            source: source.to_string(),
+            chains,
+        }
+    }
+}
+
+pub fn append(path: &str, entry: &HistoryEntry) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+// Broken lines are skipped, history is best effort
+pub fn read(path: &str) -> Vec<HistoryEntry> {
+    std::fs::read_to_string(path)
+        .map(|data| {
+            data.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Entry with the highest score for every problem, the latest one wins ties
+pub fn best_by_problem(entries: &[HistoryEntry]) -> BTreeMap<usize, &HistoryEntry> {
+    let mut best: BTreeMap<usize, &HistoryEntry> = BTreeMap::new();
+    for entry in entries {
+        match best.get(&entry.problem) {
+            Some(cur) if cur.score > entry.score => {}
+            _ => {
+                best.insert(entry.problem, entry);
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append, best_by_problem, read, HistoryEntry};
+
+    #[test]
+    fn test_append_and_read() {
+        let path = std::env::temp_dir().join(format!("history-test-{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        assert!(read(path).is_empty());
+
+        append(
+            path,
+            &HistoryEntry::new(1, 100, "optimize border", vec!["Greedy".into()]),
+        )
+        .unwrap();
+        // broken line, e.g. from interrupted write
+        std::fs::write(
+            path,
+            std::fs::read_to_string(path).unwrap() + "{\"problem\":\n",
+        )
+        .unwrap();
+        append(path, &HistoryEntry::new(2, 200, "ortools", vec![])).unwrap();
+
+        let entries = read(path);
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].problem, 1);
+        assert_eq!(entries[0].chains, vec!["Greedy".to_string()]);
+        assert_eq!(entries[1].source, "ortools");
+    }
+
+    #[test]
+    fn test_best_by_problem() {
+        let entries = vec![
+            HistoryEntry::new(1, 100, "first", vec![]),
+            HistoryEntry::new(1, 300, "second", vec![]),
+            HistoryEntry::new(1, 200, "third", vec![]),
+            HistoryEntry::new(2, 50, "first", vec![]),
+            HistoryEntry::new(2, 50, "tie", vec![]),
+        ];
+        let best = best_by_problem(&entries);
+        assert_eq!(best.len(), 2);
+        assert_eq!(best[&1].source, "second");
+        assert_eq!(best[&2].source, "tie");
+    }
+}
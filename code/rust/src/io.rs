@@ -1,4 +1,5 @@
-use crate::geom::Point;
+use crate::geom::{Point, Symmetry, Transform, Vector};
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 
@@ -7,10 +8,13 @@ pub const MUSICIAN_RADIUS_SQR: f64 = MUSICIAN_RADIUS * MUSICIAN_RADIUS;
 pub const MUSICIAN_BLOCK_RADIUS: f64 = 5.0;
 pub const SCORE_CONST: f64 = 1000000.0;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Task {
     pub room_width: f64,
     pub room_height: f64,
+    // contest rooms start in the origin, translated tasks keep their corner here
+    #[serde(default, skip_serializing_if = "is_origin")]
+    pub room_bottom_left: (f64, f64),
     pub stage_width: f64,
     pub stage_height: f64,
     pub stage_bottom_left: (f64, f64),
@@ -19,7 +23,11 @@ pub struct Task {
     pub pillars: Vec<Pillar>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+fn is_origin(p: &(f64, f64)) -> bool {
+    *p == (0.0, 0.0)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Pillar {
     pub center: (f64, f64),
     pub radius: f64,
@@ -33,6 +41,14 @@ impl Pillar {
         }
     }
 
+    pub fn transform(&self, transform: &Transform) -> Self {
+        let center = transform.apply(self.point());
+        Self {
+            center: (center.x, center.y),
+            radius: self.radius,
+        }
+    }
+
     pub fn point(&self) -> Point {
         Point {
             x: self.center.0,
@@ -41,7 +57,7 @@ impl Pillar {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Attendee {
     pub x: f64,
     pub y: f64,
@@ -64,6 +80,15 @@ impl Attendee {
         }
     }
 
+    pub fn transform(&self, transform: &Transform) -> Self {
+        let Point { x, y } = transform.apply(self.coord());
+        Self {
+            x,
+            y,
+            tastes: self.tastes.clone(),
+        }
+    }
+
     pub fn coord(&self) -> Point {
         Point {
             x: self.x,
@@ -73,6 +98,22 @@ impl Attendee {
 }
 
 impl Task {
+    pub fn room_left(&self) -> f64 {
+        self.room_bottom_left.0
+    }
+
+    pub fn room_right(&self) -> f64 {
+        self.room_bottom_left.0 + self.room_width
+    }
+
+    pub fn room_bottom(&self) -> f64 {
+        self.room_bottom_left.1
+    }
+
+    pub fn room_top(&self) -> f64 {
+        self.room_bottom_left.1 + self.room_height
+    }
+
     pub fn stage_left(&self) -> f64 {
         self.stage_bottom_left.0
     }
@@ -100,6 +141,7 @@ impl Task {
         Self {
             room_width: self.room_height,
             room_height: self.room_width,
+            room_bottom_left: (self.room_bottom_left.1, self.room_bottom_left.0),
             stage_width: self.stage_height,
             stage_height: self.stage_width,
             stage_bottom_left: (self.stage_bottom_left.1, self.stage_bottom_left.0),
@@ -109,22 +151,156 @@ impl Task {
         }
     }
 
-    pub fn simplify(self) -> Self {
-        let total_instruments = self.attendees[0].tastes.len();
-        for inst1 in 0..total_instruments {
-            for inst2 in (inst1 + 1)..total_instruments {
-                let same = self
-                    .attendees
+    pub fn transform(&self, transform: &Transform) -> Self {
+        let (room_bottom_left, room_width, room_height) = transform.rect(
+            Point {
+                x: self.room_left(),
+                y: self.room_bottom(),
+            },
+            self.room_width,
+            self.room_height,
+        );
+        let (stage_bottom_left, stage_width, stage_height) = transform.rect(
+            Point {
+                x: self.stage_left(),
+                y: self.stage_bottom(),
+            },
+            self.stage_width,
+            self.stage_height,
+        );
+        Self {
+            room_width,
+            room_height,
+            room_bottom_left: (room_bottom_left.x, room_bottom_left.y),
+            stage_width,
+            stage_height,
+            stage_bottom_left: (stage_bottom_left.x, stage_bottom_left.y),
+            musicians: self.musicians.clone(),
+            attendees: self
+                .attendees
+                .iter()
+                .map(|a| a.transform(transform))
+                .collect(),
+            pillars: self
+                .pillars
+                .iter()
+                .map(|p| p.transform(transform))
+                .collect(),
+        }
+    }
+
+    // Room symmetry in which stage is not wider than taller and audience is mostly to the bottom left
+    // of the stage, followed by translation of the stage to the origin
+    pub fn canonical_transform(&self) -> Transform {
+        let stage_center = Point {
+            x: self.stage_left() + self.stage_width / 2.0,
+            y: self.stage_bottom() + self.stage_height / 2.0,
+        };
+        let audience_direction = self
+            .attendees
+            .iter()
+            .map(|a| {
+                let v = a.coord() - stage_center;
+                let taste = a.tastes.iter().filter(|t| **t > 0.0).sum::<f64>();
+                v * (taste / v.norm2())
+            })
+            .sum::<Vector>();
+
+        let room_to_origin = Transform::translation(Vector {
+            x: -self.room_left(),
+            y: -self.room_bottom(),
+        });
+        let symmetry = Symmetry::ALL
+            .into_iter()
+            .map(|symmetry| {
+                room_to_origin.then(&Transform::for_box(
+                    symmetry,
+                    self.room_width,
+                    self.room_height,
+                ))
+            })
+            .filter(|t| {
+                let (_, w, h) = t.rect(Point::default(), self.stage_width, self.stage_height);
+                self.stage_width == self.stage_height || w < h
+            })
+            .min_by(|a, b| {
+                let key = |t: &Transform| {
+                    let v = t.apply(stage_center + audience_direction) - t.apply(stage_center);
+                    v.x + v.y
+                };
+                key(a).partial_cmp(&key(b)).unwrap()
+            })
+            .expect("Identity or transpose always fits");
+        let (stage_corner, _, _) = symmetry.rect(
+            Point {
+                x: self.stage_left(),
+                y: self.stage_bottom(),
+            },
+            self.stage_width,
+            self.stage_height,
+        );
+        symmetry.then(&Transform::translation(Vector {
+            x: -stage_corner.x,
+            y: -stage_corner.y,
+        }))
+    }
+
+    // Merges instruments which every attendee likes equally. Musicians in reduced task are ordered by
+    // instrument. Instruments affect q-factor, so tasks with pillars are kept as is.
+    pub fn simplify(self) -> (Self, TaskReduction) {
+        let total_instruments = self.instruments_len();
+        let mut instrument_map: Vec<usize> = vec![];
+        let mut kept_instruments: Vec<usize> = vec![];
+        for inst in 0..total_instruments {
+            let same = kept_instruments.iter().position(|&kept| {
+                self.attendees
                     .iter()
-                    .all(|a| a.tastes[inst1] == a.tastes[inst2]);
-                if same {
-                    println!("Every attendee has same taste for instrument {inst1} and {inst2}");
-                    // TODO replace them with single instrument and single taste
+                    .all(|a| a.tastes[inst] == a.tastes[kept])
+            });
+            match same {
+                Some(reduced) if self.pillars.is_empty() => {
+                    println!(
+                        "Every attendee has same taste for instrument {} and {inst}",
+                        kept_instruments[reduced]
+                    );
+                    instrument_map.push(reduced);
+                }
+                _ => {
+                    instrument_map.push(kept_instruments.len());
+                    kept_instruments.push(inst);
                 }
             }
         }
 
-        self
+        let mut musician_map = (0..self.musicians.len()).collect::<Vec<_>>();
+        musician_map.sort_by_key(|&idx| instrument_map[self.musicians[idx]]);
+
+        let reduced = Self {
+            musicians: musician_map
+                .iter()
+                .map(|&idx| instrument_map[self.musicians[idx]])
+                .collect(),
+            attendees: self
+                .attendees
+                .into_iter()
+                .map(|a| Attendee {
+                    tastes: kept_instruments
+                        .iter()
+                        .map(|&inst| a.tastes[inst])
+                        .collect(),
+                    ..a
+                })
+                .collect(),
+            ..self
+        };
+
+        (
+            reduced,
+            TaskReduction {
+                instrument_map,
+                musician_map,
+            },
+        )
     }
 
     pub fn musician_by_instrument(&self) -> Vec<Vec<usize>> {
@@ -136,7 +312,83 @@ impl Task {
     }
 
     pub fn instruments_len(&self) -> usize {
-        self.attendees[0].tastes.len()
+        self.attendees.first().map_or(0, |a| a.tastes.len())
+    }
+
+    // Structural checks which the rest of the code relies on
+    pub fn check(&self) -> Result<()> {
+        if self.attendees.is_empty() {
+            bail!("Task has no attendees");
+        }
+        let instruments = self.instruments_len();
+        if instruments == 0 {
+            bail!("Attendees have empty tastes");
+        }
+        if let Some((idx, _)) = self
+            .attendees
+            .iter()
+            .enumerate()
+            .find(|(_, a)| a.tastes.len() != instruments)
+        {
+            bail!(
+                "Attendee {idx} has tastes for {} instruments instead of {instruments}",
+                self.attendees[idx].tastes.len()
+            );
+        }
+        if let Some((idx, inst)) = self
+            .musicians
+            .iter()
+            .enumerate()
+            .find(|(_, inst)| **inst >= instruments)
+        {
+            bail!("Musician {idx} plays instrument {inst}, but there are only {instruments} instruments");
+        }
+        if self.stage_left() < self.room_left()
+            || self.stage_bottom() < self.room_bottom()
+            || self.stage_right() > self.room_right()
+            || self.stage_top() > self.room_top()
+        {
+            bail!("Stage is not inside of the room");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskReduction {
+    // original instrument -> instrument in reduced task
+    pub instrument_map: Vec<usize>,
+    // musician in reduced task -> original musician
+    pub musician_map: Vec<usize>,
+}
+
+impl TaskReduction {
+    pub fn reduce_solution(&self, solution: &Solution) -> Solution {
+        Solution {
+            placements: self
+                .musician_map
+                .iter()
+                .map(|&idx| solution.placements[idx])
+                .collect(),
+            volumes: self
+                .musician_map
+                .iter()
+                .map(|&idx| solution.volumes[idx])
+                .collect(),
+        }
+    }
+
+    pub fn expand_solution(&self, solution: &Solution) -> Solution {
+        let mut placements = vec![Point::default(); self.musician_map.len()];
+        let mut volumes = vec![1.0; self.musician_map.len()];
+        for (reduced_idx, &idx) in self.musician_map.iter().enumerate() {
+            placements[idx] = solution.placements[reduced_idx];
+            volumes[idx] = solution.volumes[reduced_idx];
+        }
+        Solution {
+            placements,
+            volumes,
+        }
     }
 }
 
@@ -171,6 +423,17 @@ impl Solution {
             volumes: self.volumes,
         }
     }
+
+    pub fn transform(&self, transform: &Transform) -> Self {
+        Self {
+            placements: self
+                .placements
+                .iter()
+                .map(|p| transform.apply(*p))
+                .collect(),
+            volumes: self.volumes.clone(),
+        }
+    }
 }
 
 pub fn read(path: &str) -> Task {
@@ -190,3 +453,136 @@ pub fn read_solution(path: &str) -> Solution {
     let data = fs::read_to_string(path).expect(&format!("Unable to read file {path}"));
     serde_json::from_str(&data).expect("Could not parse data")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Attendee, Pillar, Solution, Task};
+    use crate::geom::{Point, Symmetry, Transform, Vector};
+    use crate::score::{calc, calc_visibility};
+
+    fn score(task: &Task, solution: &Solution) -> i64 {
+        calc(task, solution, &calc_visibility(task, solution)).unwrap()
+    }
+
+    #[test]
+    fn test_simplify_keeps_score() {
+        // instruments 0 and 2 are the same for everybody
+        let task = Task {
+            room_width: 400.0,
+            room_height: 400.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 100.0,
+            stage_height: 100.0,
+            stage_bottom_left: (150.0, 150.0),
+            musicians: vec![2, 0, 1, 2, 0],
+            attendees: vec![
+                Attendee {
+                    x: 50.0,
+                    y: 50.0,
+                    tastes: vec![1000.0, -300.0, 1000.0],
+                },
+                Attendee {
+                    x: 350.0,
+                    y: 200.0,
+                    tastes: vec![-200.0, 700.0, -200.0],
+                },
+                Attendee {
+                    x: 200.0,
+                    y: 380.0,
+                    tastes: vec![500.0, 500.0, 500.0],
+                },
+            ],
+            pillars: vec![],
+        };
+        let solution = Solution {
+            placements: vec![
+                Point { x: 165.0, y: 165.0 },
+                Point { x: 200.0, y: 170.0 },
+                Point { x: 235.0, y: 200.0 },
+                Point { x: 170.0, y: 230.0 },
+                Point { x: 210.0, y: 235.0 },
+            ],
+            volumes: vec![10.0, 0.0, 3.0, 1.0, 7.5],
+        };
+        let full_score = score(&task, &solution);
+
+        let (reduced_task, reduction) = task.clone().simplify();
+        assert_eq!(reduced_task.instruments_len(), 2);
+        let reduced_solution = reduction.reduce_solution(&solution);
+        assert_eq!(score(&reduced_task, &reduced_solution), full_score);
+
+        let expanded = reduction.expand_solution(&reduced_solution);
+        assert_eq!(expanded.placements, solution.placements);
+        assert_eq!(expanded.volumes, solution.volumes);
+        assert_eq!(score(&task, &expanded), full_score);
+    }
+
+    #[test]
+    fn test_transform_keeps_score() {
+        let task = Task {
+            room_width: 400.0,
+            room_height: 300.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 60.0,
+            stage_height: 100.0,
+            stage_bottom_left: (200.0, 50.0),
+            musicians: vec![0, 1, 0],
+            attendees: vec![
+                Attendee {
+                    x: 50.0,
+                    y: 100.0,
+                    tastes: vec![1000.0, -300.0],
+                },
+                Attendee {
+                    x: 350.0,
+                    y: 250.0,
+                    tastes: vec![200.0, 700.0],
+                },
+            ],
+            pillars: vec![Pillar {
+                center: (100.0, 110.0),
+                radius: 5.0,
+            }],
+        };
+        let solution = Solution {
+            placements: vec![
+                Point { x: 215.0, y: 65.0 },
+                Point { x: 240.0, y: 100.0 },
+                Point { x: 215.0, y: 135.0 },
+            ],
+            volumes: vec![1.0, 5.0, 10.0],
+        };
+        let full_score = score(&task, &solution);
+
+        let transforms = Symmetry::ALL
+            .into_iter()
+            .map(|symmetry| Transform::for_box(symmetry, task.room_width, task.room_height))
+            .chain([
+                Transform::translation(Vector { x: -200.0, y: 70.0 }),
+                task.canonical_transform(),
+            ]);
+        for transform in transforms {
+            let moved_task = task.transform(&transform);
+            let moved_solution = solution.transform(&transform);
+            assert!(moved_task.check().is_ok());
+            assert_eq!(score(&moved_task, &moved_solution), full_score);
+
+            let inverse = transform.inverse();
+            let back_task = moved_task.transform(&inverse);
+            let back_solution = moved_solution.transform(&inverse);
+            assert_eq!(score(&back_task, &back_solution), full_score);
+            assert!((back_task.stage_left() - task.stage_left()).abs() < 1e-9);
+            assert!((back_task.stage_bottom() - task.stage_bottom()).abs() < 1e-9);
+        }
+
+        let canonical = task.transform(&task.canonical_transform());
+        assert!(canonical.stage_width <= canonical.stage_height);
+        assert!(canonical.stage_left().abs() < 1e-9 && canonical.stage_bottom().abs() < 1e-9);
+
+        // translated room keeps its corner, translating it back restores the task
+        let translation = Transform::translation(Vector { x: -200.0, y: 70.0 });
+        let moved_task = task.transform(&translation);
+        assert_eq!(moved_task.room_bottom_left, (-200.0, 70.0));
+        assert_eq!(moved_task.transform(&translation.inverse()), task);
+    }
+}
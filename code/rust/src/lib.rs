@@ -0,0 +1,20 @@
+pub mod analysis;
+pub mod candidates;
+pub mod constructor;
+#[cfg(feature = "contest")]
+pub mod contest;
+pub mod diff;
+pub mod evolution;
+pub mod generator;
+#[cfg(feature = "genetic")]
+pub mod genetics;
+pub mod geom;
+pub mod history;
+pub mod io;
+pub mod lns;
+pub mod optimizer;
+pub mod planner;
+pub mod report;
+pub mod score;
+pub mod solution;
+pub mod storage;
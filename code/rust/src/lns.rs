@@ -0,0 +1,240 @@
+use crate::candidates::Candidates;
+use crate::geom::Point;
+use crate::io::{default_volumes_task, Solution, Task, MUSICIAN_RADIUS_SQR};
+use crate::optimizer::random_point_on_stage;
+use crate::score::{
+    attendee_score_without_q, calc, calc_ex, calc_points_visibility, calc_visibility_fast,
+    Visibility, MIN_SCORE,
+};
+use crate::solution::recalc_volumes;
+use float_ord::FloatOrd;
+use rand::Rng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+// Lattice depends only on the task, and optimizer chains call LNS many times for the same task
+static LATTICE_CACHE: Mutex<Option<(Task, Arc<Candidates>)>> = Mutex::new(None);
+
+pub struct LnsParams {
+    iterations: usize,
+    // amount of musician x (musician + attendee) pairs we can afford to evaluate per call
+    pairs_budget: usize,
+    max_destroyed: usize,
+    random_candidates: usize,
+    lattice_candidates_per_instrument: usize,
+}
+
+impl Default for LnsParams {
+    fn default() -> Self {
+        LnsParams {
+            iterations: 30,
+            pairs_budget: 100_000_000,
+            max_destroyed: 20,
+            random_candidates: 200,
+            lattice_candidates_per_instrument: 50,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Destroy {
+    Region,
+    Instrument,
+    LowContribution,
+}
+
+const DESTROY_OPERATORS: [Destroy; 3] = [
+    Destroy::Region,
+    Destroy::Instrument,
+    Destroy::LowContribution,
+];
+
+fn destroy(
+    task: &Task,
+    solution: &Solution,
+    visibility: &Visibility,
+    operator: Destroy,
+    count: usize,
+    rng: &mut Xoshiro256PlusPlus,
+) -> Vec<usize> {
+    let musicians = solution.placements.len();
+    match operator {
+        Destroy::Region => {
+            let center = solution.placements[rng.gen_range(0..musicians)];
+            let mut indices = (0..musicians).collect::<Vec<_>>();
+            indices.sort_by_key(|idx| FloatOrd(solution.placements[*idx].dist_sqr(center)));
+            indices.truncate(count);
+            indices
+        }
+        Destroy::Instrument => {
+            let instrument = task.musicians[rng.gen_range(0..musicians)];
+            let indices = task.musician_by_instrument()[instrument].clone();
+            if indices.len() <= count {
+                indices
+            } else {
+                rand::seq::index::sample(rng, indices.len(), count)
+                    .into_iter()
+                    .map(|i| indices[i])
+                    .collect()
+            }
+        }
+        Destroy::LowContribution => {
+            let score = calc_ex(task, solution, visibility);
+            let mut indices = (0..musicians).collect::<Vec<_>>();
+            indices.sort_by_key(|idx| score.musician[*idx]);
+            indices.truncate(count);
+            indices
+        }
+    }
+}
+
+fn cached_lattice(task: &Task) -> Arc<Candidates> {
+    let mut cache = LATTICE_CACHE.lock().unwrap();
+    if let Some((cached_task, lattice)) = cache.as_ref() {
+        if cached_task == task {
+            return lattice.clone();
+        }
+    }
+    let lattice = Arc::new(Candidates::for_task(task));
+    *cache = Some((task.clone(), lattice.clone()));
+    lattice
+}
+
+// Places removed musicians back one by one, each time taking the best (musician, candidate) pair
+// which does not collide with already placed musicians
+fn repair(
+    task: &Task,
+    solution: &Solution,
+    removed: &[usize],
+    lattice: &Candidates,
+    params: &LnsParams,
+    rng: &mut Xoshiro256PlusPlus,
+) -> Option<Solution> {
+    let kept = (0..solution.placements.len())
+        .filter(|idx| !removed.contains(idx))
+        .map(|idx| solution.placements[idx])
+        .collect::<Vec<_>>();
+
+    let is_free = |p: Point, placed: &[Point]| {
+        kept.iter()
+            .chain(placed.iter())
+            .all(|other| p.dist_sqr(*other) >= MUSICIAN_RADIUS_SQR)
+    };
+
+    let mut candidates = removed
+        .iter()
+        .map(|idx| solution.placements[*idx])
+        .collect::<Vec<_>>();
+    for _ in 0..params.random_candidates {
+        let p = random_point_on_stage(task, rng);
+        if is_free(p, &[]) {
+            candidates.push(p);
+        }
+    }
+    let instruments = removed
+        .iter()
+        .map(|idx| task.musicians[*idx])
+        .collect::<BTreeSet<_>>();
+    for instrument in instruments {
+        candidates.extend(
+            lattice
+                .best_for_instrument(instrument)
+                .into_iter()
+                .map(|idx| lattice.positions[idx])
+                .filter(|p| is_free(*p, &[]))
+                .take(params.lattice_candidates_per_instrument),
+        );
+    }
+
+    let candidates_visibility = calc_points_visibility(task, &kept, &candidates);
+    let mut moves = vec![];
+    for &mus_idx in removed {
+        let instrument = task.musicians[mus_idx];
+        for (cand_idx, candidate) in candidates.iter().enumerate() {
+            let score = task
+                .attendees
+                .iter()
+                .enumerate()
+                .filter(|(att_idx, _)| candidates_visibility[cand_idx][*att_idx])
+                .map(|(_, att)| attendee_score_without_q(att, instrument, *candidate))
+                .sum::<i64>();
+            // silent musician scores zero wherever it is
+            moves.push((score.max(0), mus_idx, cand_idx));
+        }
+    }
+    moves.sort_by_key(|(score, _, _)| -score);
+
+    let mut result = solution.clone();
+    let mut placed = vec![];
+    let mut is_placed = vec![false; solution.placements.len()];
+    for (_, mus_idx, cand_idx) in moves {
+        if is_placed[mus_idx] || !is_free(candidates[cand_idx], &placed) {
+            continue;
+        }
+        result.placements[mus_idx] = candidates[cand_idx];
+        placed.push(candidates[cand_idx]);
+        is_placed[mus_idx] = true;
+    }
+
+    if removed.iter().all(|idx| is_placed[*idx]) {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+pub fn large_neighbourhood_search(
+    task: &Task,
+    initial_solution: &Solution,
+    visibility: &Visibility,
+    params: &LnsParams,
+    rng: &mut Xoshiro256PlusPlus,
+) -> (Solution, Visibility) {
+    let musicians = initial_solution.placements.len();
+    if musicians < 2 {
+        return (initial_solution.clone(), visibility.clone());
+    }
+
+    let mut best = initial_solution.clone();
+    best.volumes = default_volumes_task(task);
+    recalc_volumes(task, &mut best, visibility);
+    let mut best_visibility = visibility.clone();
+    let mut best_score = calc(task, &best, &best_visibility).unwrap_or(MIN_SCORE);
+
+    let pairs = task.musicians.len() * (task.musicians.len() + task.attendees.len());
+    let iterations = (params.pairs_budget / pairs.max(1)).clamp(1, params.iterations);
+    let lattice = cached_lattice(task);
+    let count = (musicians / 10)
+        .clamp(2, params.max_destroyed)
+        .min(musicians);
+
+    for _ in 0..iterations {
+        let operator = DESTROY_OPERATORS[rng.gen_range(0..DESTROY_OPERATORS.len())];
+        let removed = destroy(task, &best, &best_visibility, operator, count, rng);
+        let Some(mut solution) = repair(task, &best, &removed, &lattice, params, rng) else {
+            continue;
+        };
+
+        solution.volumes = default_volumes_task(task);
+        let visibility = calc_visibility_fast(task, &solution);
+        recalc_volumes(task, &mut solution, &visibility);
+        let score = calc(task, &solution, &visibility).unwrap_or(MIN_SCORE);
+        if score > best_score {
+            best = solution;
+            best_visibility = visibility;
+            best_score = score;
+        }
+    }
+
+    (best, best_visibility)
+}
+
+pub fn lns_optimizer(
+    task: &Task,
+    initial_solution: &Solution,
+    visibility: &Visibility,
+    rng: &mut Xoshiro256PlusPlus,
+) -> (Solution, Visibility) {
+    large_neighbourhood_search(task, initial_solution, visibility, &Default::default(), rng)
+}
@@ -1,109 +1,29 @@
-#[macro_use]
-extern crate rouille;
-
-use crate::io::default_volumes_task;
-use crate::optimizer::one_by_one_do_talogo;
-use crate::score::potential_score;
-use crate::solution::{dummy, recalc_volumes};
 use clap::{self, arg, value_parser};
-use io::{Solution, Task};
+use icfpc2023::constructor::{self, Constructor};
+#[cfg(feature = "contest")]
+use icfpc2023::contest;
+use icfpc2023::history::{self, HISTORY_FILE};
+use icfpc2023::io::{default_volumes_task, Solution};
+use icfpc2023::optimizer::{
+    one_by_one_do_talogo, optimize_do_talogo_with_chains, FINAL_OPTIMIZERS,
+};
+use icfpc2023::score::potential_score;
+use icfpc2023::solution::recalc_volumes;
+use icfpc2023::storage::{
+    get_base_solution, get_manual_solution, get_optimal_solution, historical_solution_dirs,
+    read_task, task_exists, write_optimal_solution, BASE_SOLUTIONS_DIR, OPTIMAL_SOLUTIONS_DIR,
+    TASKS_NUM,
+};
+#[cfg(feature = "contest")]
+use icfpc2023::storage::{DATA_DIR, SUBMISSIONS_LOG, SUBMIT_QUEUE, TOKEN_FILE};
+use icfpc2023::{analysis, diff, generator, geom, io, planner, report, score};
 use num_format::{Locale, ToFormattedString};
-use optimizer::optimize_do_talogo;
-
-mod genetics;
-mod geom;
-mod http_api;
-mod io;
-mod optimizer;
-mod score;
-mod solution;
-
-const BASE_SOLUTIONS_DIR: &str = "../../solutions-20230708-124428";
-const OPTIMAL_SOLUTIONS_DIR: &str = "../../solutions";
-const MANUAL_SOLUTIONS_DIR: &str = "../../solutions-manual";
-const ORTOOLS_DATA_DIR: &str = "../../ortools-data";
-const TASKS_NUM: usize = 90;
-
-fn get_solution(task: &Task, solution_path: &str) -> Solution {
-    if std::fs::metadata(solution_path).is_ok() {
-        io::read_solution(solution_path)
-    } else {
-        let sol = dummy(&task);
-        io::write(solution_path, &sol);
-        sol
-    }
-}
-
-fn get_base_solution(task: &Task, i: usize) -> Solution {
-    get_solution(task, &format!("{BASE_SOLUTIONS_DIR}/problem-{i}.json"))
-}
-
-fn get_optimal_solution(task: &Task, i: usize) -> Solution {
-    get_solution(task, &format!("{OPTIMAL_SOLUTIONS_DIR}/problem-{i}.json"))
-}
-
-fn get_manual_solution(task: &Task, i: usize) -> Solution {
-    let solution_path = format!("{MANUAL_SOLUTIONS_DIR}/problem-{i}.json");
-    if std::fs::metadata(&solution_path).is_ok() {
-        io::read_solution(&solution_path)
-    } else {
-        get_optimal_solution(task, i)
-    }
-}
-
-pub fn read_task(i: usize) -> Task {
-    io::read(&format!("../../data/problem-{i}.json"))
-}
-
-pub fn write_optimal_solution(task: &Task, solution: &Solution, points: i64, i: usize) {
-    let cur_solution = get_optimal_solution(task, i);
-    let visibility = score::calc_visibility(&task, &cur_solution);
-
-    match score::calc(&task, &cur_solution, &visibility) {
-        Ok(cur_points) => {
-            if cur_points > points {
-                println!(
-                    "Solution for task {i} was not improved (currently {cur_points}, updated {points})"
-                );
-                return;
-            } else if cur_points == points {
-                println!("Solution for task {i} did not change");
-                return;
-            } else {
-                println!("+++Solution for task {i} was improved from {cur_points} to {points}");
-            }
-        }
-        Err(_) => {
-            println!("Solution for task {i} was incorrect, got {points} points");
-        }
-    };
 
-    io::write(
-        &format!("{OPTIMAL_SOLUTIONS_DIR}/problem-{i}.json"),
-        &solution,
-    );
-}
-
-pub fn write_manual_solution(solution: &Solution, i: usize) {
-    io::write(
-        &format!("{MANUAL_SOLUTIONS_DIR}/problem-{i}.json"),
-        &solution,
-    );
-}
-
-fn get_spread_solution(task: &Task) -> Solution {
-    [5.0, 3.0, 2.0, 1.5, 1.1, 1.05, 1.01, 1.005, 1.001, 1.0]
-        .into_iter()
-        .map(|spread| solution::dummy_hex(&task, spread, true))
-        .filter_map(|solution| {
-            let visibility = score::calc_visibility(&task, &solution);
-            score::calc(&task, &solution, &visibility)
-                .ok()
-                .map(|_| solution)
-        })
-        .next()
-        .unwrap()
-}
+const ORTOOLS_DATA_DIR: &str = "../../ortools-data";
+// bases of optimize subcommand besides constructors
+const BASE_SOLUTIONS: [&str; 4] = ["dummy", "optimal", "manual", "auto"];
+#[cfg(feature = "physics")]
+const RAPIER_TUNING_FILE: &str = "../../rapier-tuning.json";
 
 fn main() {
     let cmd = clap::Command::new("rust")
@@ -113,13 +33,32 @@ fn main() {
             clap::command!("optimize")
                 .arg(
                     arg!([base])
-                        .value_parser(value_parser!(String))
+                        .help(format!(
+                            "{} or constructor: {}",
+                            BASE_SOLUTIONS.join(", "),
+                            Constructor::ALL.map(Constructor::name).join(", ")
+                        ))
+                        .value_parser(clap::builder::PossibleValuesParser::new(
+                            BASE_SOLUTIONS
+                                .into_iter()
+                                .chain(Constructor::ALL.map(Constructor::name)),
+                        ))
+                        // already listed in the help
+                        .hide_possible_values(true)
                         .default_value("dummy"),
                 )
                 .arg(arg!([id]).value_parser(value_parser!(usize))),
         )
         .subcommand(clap::command!("potential"))
-        .subcommand(clap::command!("server"))
+        .subcommand(
+            clap::command!("report")
+                .arg(
+                    arg!(--format <FORMAT>)
+                        .value_parser(["markdown", "csv", "json"])
+                        .default_value("markdown"),
+                )
+                .arg(arg!(--output <PATH> "Where to write report, stdout if not specified")),
+        )
         .subcommand(
             clap::command!("make-ortools-input").arg(arg!([id]).value_parser(value_parser!(usize))),
         )
@@ -127,7 +66,128 @@ fn main() {
             clap::command!("apply-ortools-output")
                 .arg(arg!([id]).value_parser(value_parser!(usize))),
         )
-        .subcommand(clap::command!("recalc-volumes"));
+        .subcommand(clap::command!("recalc-volumes"))
+        .subcommand(
+            clap::command!("score")
+                .arg(
+                    arg!([task] "Task file")
+                        .required_unless_present("problem")
+                        .conflicts_with("problem"),
+                )
+                .arg(
+                    arg!([solution_file] "Solution file")
+                        .required_unless_present_any(["problem", "solution"]),
+                )
+                .arg(
+                    arg!(--problem <ID> "Problem id instead of task file")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(arg!(--solution <PATH> "Solution file, best known solution by default"))
+                .arg(arg!(--json "Print result as json")),
+        )
+        .subcommand(
+            clap::command!("diff")
+                .arg(arg!(<before> "Solution file to compare with"))
+                .arg(arg!(<after> "Updated solution file"))
+                .arg(
+                    arg!(--task <PATH> "Task file")
+                        .required_unless_present("problem")
+                        .conflicts_with("problem"),
+                )
+                .arg(
+                    arg!(--problem <ID> "Problem id instead of task file")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    arg!(--top <COUNT> "How many attendees with biggest changes to print")
+                        .value_parser(value_parser!(usize))
+                        .default_value("10"),
+                )
+                .arg(arg!(--json "Print result as json")),
+        )
+        .subcommand(
+            clap::command!("generate")
+                .arg(arg!([output] "Where to write task, stdout if not specified"))
+                .arg(arg!(--seed <SEED>).value_parser(value_parser!(u64)))
+                .arg(arg!(--"room-width" <WIDTH>).value_parser(value_parser!(f64)))
+                .arg(arg!(--"room-height" <HEIGHT>).value_parser(value_parser!(f64)))
+                .arg(arg!(--"stage-width" <WIDTH>).value_parser(value_parser!(f64)))
+                .arg(arg!(--"stage-height" <HEIGHT>).value_parser(value_parser!(f64)))
+                .arg(arg!(--musicians <COUNT>).value_parser(value_parser!(usize)))
+                .arg(arg!(--instruments <COUNT>).value_parser(value_parser!(usize)))
+                .arg(arg!(--attendees <COUNT>).value_parser(value_parser!(usize)))
+                .arg(arg!(--pillars <COUNT>).value_parser(value_parser!(usize)))
+                .arg(
+                    arg!(--"attendee-distribution" <DISTRIBUTION>).value_parser([
+                        "uniform",
+                        "clustered",
+                        "ring",
+                    ]),
+                )
+                .arg(arg!(--"taste-distribution" <DISTRIBUTION>).value_parser([
+                    "uniform",
+                    "positive",
+                    "polarized",
+                ])),
+        )
+        .subcommand(
+            clap::command!("plan")
+                .arg(
+                    arg!([id] "Problem id, all problems if not specified")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(arg!(--json "Print result as json")),
+        )
+        .subcommand(
+            clap::command!("analyse")
+                .arg(
+                    arg!([id] "Problem id, all problems if not specified")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(arg!(--task <PATH> "Task file instead of problem id"))
+                .arg(arg!(--json "Print result as json")),
+        );
+    #[cfg(feature = "physics")]
+    let cmd = cmd.subcommand(
+        clap::command!("tune-rapier").arg(arg!([id]).value_parser(value_parser!(usize))),
+    );
+    #[cfg(feature = "contest")]
+    let cmd = cmd.subcommand(
+        clap::command!("submit")
+            .arg(
+                arg!([id] "Problem id, all problems if not specified")
+                    .value_parser(value_parser!(usize)),
+            )
+            .arg(
+                arg!(--solution <PATH> "Solution file, best known solution by default")
+                    .requires("id"),
+            )
+            .arg(
+                arg!(--"base-url" <URL> "Contest api url").default_value(contest::DEFAULT_BASE_URL),
+            )
+            .arg(arg!(--token <PATH> "File with api token").default_value(TOKEN_FILE))
+            .arg(arg!(--log <PATH> "Log of all submissions").default_value(SUBMISSIONS_LOG))
+            .arg(
+                arg!(--queue <PATH> "Solutions waiting to be sent, survives restarts")
+                    .default_value(SUBMIT_QUEUE),
+            )
+            .arg(
+                arg!(--"interval-ms" <MS> "Minimal time between requests")
+                    .value_parser(value_parser!(u64))
+                    .default_value("1000"),
+            ),
+    );
+    #[cfg(feature = "contest")]
+    let cmd = cmd.subcommand(
+        clap::command!("fetch")
+            .arg(
+                arg!([id] "Problem id, all problems if not specified")
+                    .value_parser(value_parser!(usize)),
+            )
+            .arg(
+                arg!(--"base-url" <URL> "Contest api url").default_value(contest::DEFAULT_BASE_URL),
+            ),
+    );
     let matches = cmd.get_matches();
     match matches.subcommand() {
         Some(("potential", _matches)) => {
@@ -151,6 +211,41 @@ fn main() {
             }
         }
 
+        Some(("report", matches)) => {
+            let history = history::read(HISTORY_FILE);
+            let best_history = history::best_by_problem(&history);
+            let historical_dirs = historical_solution_dirs();
+
+            let reports = (1..=TASKS_NUM)
+                .filter(|i| task_exists(*i))
+                .map(|i| {
+                    let task = read_task(i);
+                    report::problem_report(
+                        i,
+                        &task,
+                        OPTIMAL_SOLUTIONS_DIR,
+                        &historical_dirs,
+                        best_history.get(&i).copied(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let data = match matches.get_one::<String>("format").unwrap().as_str() {
+                "csv" => report::to_csv(&reports),
+                "json" => {
+                    serde_json::to_string_pretty(&reports).expect("Could not serialize report")
+                }
+                _ => report::to_markdown(&reports),
+            };
+            match matches.get_one::<String>("output") {
+                Some(path) => {
+                    std::fs::write(path, data).expect("Got error when writing to file");
+                    println!("Report for {} problems written to {path}", reports.len());
+                }
+                None => print!("{data}"),
+            }
+        }
+
         Some(("optimize", matches)) => {
             let base_solution_name = matches
                 .get_one::<String>("base")
@@ -160,15 +255,58 @@ fn main() {
                 Some(&id) => id..=id,
                 None => 1..=TASKS_NUM,
             };
+            #[cfg(feature = "physics")]
+            let rapier_tuning = icfpc2023::optimizer::rap::read_tuning(RAPIER_TUNING_FILE);
+            let history_records = if base_solution_name == "auto" {
+                planner::history_records(&history::read(HISTORY_FILE))
+            } else {
+                vec![]
+            };
             for i in range {
                 println!("===================================");
                 let task = read_task(i);
+                let mut base_solution_name = base_solution_name.clone();
+                let mut optimizers = FINAL_OPTIMIZERS.to_vec();
                 let base_solution = match base_solution_name.as_str() {
                     "dummy" => get_base_solution(&task, i),
-                    "spread" => get_spread_solution(&task),
                     "optimal" => get_optimal_solution(&task, i),
                     "manual" => get_manual_solution(&task, i),
-                    _ => panic!("Unknown base solution {base_solution_name}"),
+                    "auto" => {
+                        let plan = match planner::plan(&task, &history_records) {
+                            Ok(plan) => plan,
+                            Err(err) => {
+                                println!("Could not plan task {i}: {err}");
+                                continue;
+                            }
+                        };
+                        for reason in &plan.reasons {
+                            println!("Plan for task {i}: {reason}");
+                        }
+                        let (constructor, solution) = match plan.build(&task) {
+                            Ok(res) => res,
+                            Err(err) => {
+                                println!("Could not build solution for task {i}: {err}");
+                                continue;
+                            }
+                        };
+                        base_solution_name = constructor.name().to_string();
+                        optimizers = plan.optimizers;
+                        solution
+                    }
+                    name => {
+                        let constructor =
+                            Constructor::from_name(name).expect("clap checks base names");
+                        match constructor::build_with_fallback(&task, &[constructor]) {
+                            Ok((constructor, solution)) => {
+                                base_solution_name = constructor.name().to_string();
+                                solution
+                            }
+                            Err(err) => {
+                                println!("Could not build solution for task {i}: {err}");
+                                continue;
+                            }
+                        }
+                    }
                 };
                 let visibility = score::calc_visibility(&task, &base_solution);
 
@@ -178,13 +316,47 @@ fn main() {
 
                         let one_by_one = false;
 
-                        let (best_solution, visibility) = if one_by_one {
-                            one_by_one_do_talogo(&task, &base_solution, &visibility)
+                        #[cfg(feature = "physics")]
+                        if let Some(preset) = icfpc2023::optimizer::rap::apply_tuning(
+                            &mut optimizers,
+                            &rapier_tuning,
+                            i,
+                        ) {
+                            println!("Using tuned rapier preset {preset} for task {i}");
+                        }
+
+                        let (reduced_task, reduction) = task.clone().simplify();
+                        let reduced_solution = reduction.reduce_solution(&base_solution);
+                        let reduced_visibility =
+                            score::calc_visibility_fast(&reduced_task, &reduced_solution);
+
+                        let (best_solution, chains) = if one_by_one {
+                            let (solution, _) = one_by_one_do_talogo(
+                                &reduced_task,
+                                &reduced_solution,
+                                &reduced_visibility,
+                            );
+                            (solution, vec!["one by one".to_string()])
                         } else {
-                            optimize_do_talogo(&task, &base_solution, visibility)
+                            let (solution, _, chains) = optimize_do_talogo_with_chains(
+                                &reduced_task,
+                                &reduced_solution,
+                                reduced_visibility,
+                                &optimizers,
+                            );
+                            (solution, chains)
                         };
+                        let best_solution = reduction.expand_solution(&best_solution);
+                        let visibility = score::calc_visibility_fast(&task, &best_solution);
                         match score::calc(&task, &best_solution, &visibility) {
-                            Ok(points) => write_optimal_solution(&task, &best_solution, points, i),
+                            Ok(points) => write_optimal_solution(
+                                &task,
+                                &best_solution,
+                                points,
+                                i,
+                                &format!("optimize {base_solution_name}"),
+                                chains,
+                            ),
                             Err(_) => {
                                 println!("Could not find correct solution for task {i}");
                             }
@@ -283,7 +455,7 @@ fn main() {
                 match score::calc(&task, &solution, &visibility) {
                     Ok(points) => {
                         println!("ortools solution for task {i} got {points} points");
-                        write_optimal_solution(&task, &solution, points, i);
+                        write_optimal_solution(&task, &solution, points, i, "ortools", vec![]);
                     }
                     Err(err) => {
                         println!("ortools solution from for task {i} is incorrect: {err}");
@@ -301,7 +473,14 @@ fn main() {
                 match score::calc(&task, &solution, &visibility) {
                     Ok(points) => {
                         println!("ortools solution for task {i} got {points} points");
-                        write_optimal_solution(&task, &solution, points, i);
+                        write_optimal_solution(
+                            &task,
+                            &solution,
+                            points,
+                            i,
+                            "recalc volumes",
+                            vec![],
+                        );
                     }
                     Err(err) => {
                         println!("ortools solution from for task {i} is incorrect: {err}");
@@ -310,6 +489,471 @@ fn main() {
             }
         }
 
+        #[cfg(feature = "physics")]
+        Some(("tune-rapier", matches)) => {
+            use icfpc2023::optimizer;
+            use rand::SeedableRng;
+            use rand_xoshiro::Xoshiro256PlusPlus;
+
+            let id = matches.get_one::<usize>("id");
+            let range = match id {
+                Some(&id) => id..=id,
+                None => 1..=TASKS_NUM,
+            };
+
+            let mut tuning = optimizer::rap::read_tuning(RAPIER_TUNING_FILE);
+
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+            for i in range {
+                println!("===================================");
+                let task = read_task(i);
+                let solution = get_optimal_solution(&task, i);
+                let visibility = score::calc_visibility_fast(&task, &solution);
+
+                let results = optimizer::rap::tune(&task, &solution, &visibility, &mut rng);
+                tuning.insert(
+                    i,
+                    results
+                        .iter()
+                        .map(|(name, points, _, _)| (name.to_string(), *points))
+                        .collect(),
+                );
+                let (best_name, best_points, best_solution, _) = &results[0];
+                println!("Best rapier preset for task {i} is {best_name}");
+                if score::validate(&task, best_solution).is_ok() {
+                    write_optimal_solution(
+                        &task,
+                        best_solution,
+                        *best_points,
+                        i,
+                        "tune rapier",
+                        vec![best_name.to_string()],
+                    );
+                }
+
+                optimizer::rap::write_tuning(RAPIER_TUNING_FILE, &tuning)
+                    .expect("Got error when writing to file");
+            }
+        }
+
+        Some(("score", matches)) => {
+            let (task, solution) = match matches.get_one::<usize>("problem") {
+                Some(&i) => {
+                    let solution_path = match matches.get_one::<String>("solution") {
+                        Some(path) => path.clone(),
+                        None => format!("{OPTIMAL_SOLUTIONS_DIR}/problem-{i}.json"),
+                    };
+                    // scoring should not create dummy solution like get_optimal_solution does
+                    if std::fs::metadata(&solution_path).is_err() {
+                        println!("No solution file {solution_path}");
+                        std::process::exit(1);
+                    }
+                    (read_task(i), io::read_solution(&solution_path))
+                }
+                None => {
+                    // clap makes sure task and one of solution arguments are present
+                    let task_path = matches.get_one::<String>("task").unwrap();
+                    let solution_path = matches
+                        .get_one::<String>("solution_file")
+                        .or(matches.get_one::<String>("solution"))
+                        .unwrap();
+                    (io::read(task_path), io::read_solution(solution_path))
+                }
+            };
+            let json = matches.get_flag("json");
+
+            match score::calc_breakdown(&task, &solution) {
+                Ok(breakdown) if json => {
+                    println!(
+                        "{}",
+                        serde_json::to_string(&breakdown).expect("Could not serialize score")
+                    );
+                }
+                Ok(breakdown) => {
+                    println!(
+                        "Score: {}",
+                        breakdown.score.to_formatted_string(&Locale::en)
+                    );
+                    println!(
+                        "Blocked pairs: {} of {}",
+                        breakdown.blocked_pairs,
+                        task.attendees.len() * task.musicians.len()
+                    );
+                    println!("By instrument:");
+                    for (instrument, points) in breakdown.instrument.iter().enumerate() {
+                        println!(
+                            "  {instrument:4} {:>15}",
+                            points.to_formatted_string(&Locale::en)
+                        );
+                    }
+                    println!("By musician:");
+                    for (mus_idx, points) in breakdown.musician.iter().enumerate() {
+                        println!(
+                            "  {mus_idx:4} (instrument {:4}, volume {:4.1}) {:>15}",
+                            task.musicians[mus_idx],
+                            solution.volumes[mus_idx],
+                            points.to_formatted_string(&Locale::en)
+                        );
+                    }
+                }
+                Err(err) => {
+                    if json {
+                        println!("{}", serde_json::json!({ "error": err.to_string() }));
+                    } else {
+                        println!("Solution is incorrect: {err}");
+                    }
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Some(("diff", matches)) => {
+            // clap makes sure that exactly one of them is present
+            let task = match matches.get_one::<usize>("problem") {
+                Some(&i) => read_task(i),
+                None => io::read(matches.get_one::<String>("task").unwrap()),
+            };
+            let before = io::read_solution(matches.get_one::<String>("before").unwrap());
+            let after = io::read_solution(matches.get_one::<String>("after").unwrap());
+
+            let diff = match diff::diff(&task, &before, &after) {
+                Ok(diff) => diff,
+                Err(err) => {
+                    println!("Could not compare solutions: {err:#}");
+                    std::process::exit(1);
+                }
+            };
+            if matches.get_flag("json") {
+                println!(
+                    "{}",
+                    serde_json::to_string(&diff).expect("Could not serialize diff")
+                );
+                return;
+            }
+
+            println!(
+                "Score: {} -> {} ({:+})",
+                diff.score_before.to_formatted_string(&Locale::en),
+                diff.score_after.to_formatted_string(&Locale::en),
+                diff.score_after - diff.score_before
+            );
+            println!("Moved musicians: {}", diff.moves.len());
+            for m in &diff.moves {
+                println!(
+                    "  {:4} ({:.2}, {:.2}) -> ({:.2}, {:.2}), {:.2} away, {:+}",
+                    m.musician,
+                    m.from.x,
+                    m.from.y,
+                    m.to.x,
+                    m.to.y,
+                    m.distance,
+                    diff.musician_delta[m.musician]
+                );
+            }
+            println!("Volume changes: {}", diff.volume_changes.len());
+            for v in &diff.volume_changes {
+                println!(
+                    "  {:4} {:.1} -> {:.1}, {:+}",
+                    v.musician, v.before, v.after, diff.musician_delta[v.musician]
+                );
+            }
+            println!(
+                "Newly blocked pairs: {}, newly unblocked pairs: {}",
+                diff.newly_blocked.len(),
+                diff.newly_unblocked.len()
+            );
+
+            let top = *matches.get_one::<usize>("top").unwrap();
+            let mut attendees = (0..task.attendees.len())
+                .filter(|idx| diff.attendee_delta[*idx] != 0)
+                .collect::<Vec<_>>();
+            attendees.sort_by_key(|idx| -diff.attendee_delta[*idx].abs());
+            println!(
+                "Attendees with changed score: {}, biggest changes:",
+                attendees.len()
+            );
+            for idx in attendees.into_iter().take(top) {
+                println!("  {idx:5} {:+}", diff.attendee_delta[idx]);
+            }
+        }
+
+        #[cfg(feature = "contest")]
+        Some(("submit", matches)) => {
+            let range = match matches.get_one::<usize>("id") {
+                Some(&id) => id..=id,
+                None => 1..=TASKS_NUM,
+            };
+            let token = std::fs::read_to_string(matches.get_one::<String>("token").unwrap())
+                .ok()
+                .map(|token| token.trim().to_string());
+            if token.is_none() {
+                println!("No api token found, submitting without authorization");
+            }
+            let client =
+                contest::ContestClient::new(matches.get_one::<String>("base-url").unwrap(), token);
+
+            let mut queue = vec![];
+            for i in range.filter(|i| task_exists(*i)) {
+                let path = match matches.get_one::<String>("solution") {
+                    Some(path) => path.clone(),
+                    None => format!("{OPTIMAL_SOLUTIONS_DIR}/problem-{i}.json"),
+                };
+                let Ok(contents) = std::fs::read_to_string(&path) else {
+                    println!("No solution for task {i} in {path}");
+                    continue;
+                };
+
+                // never send solutions which our scorer rejects
+                let task = read_task(i);
+                let solution: Solution = match serde_json::from_str(&contents) {
+                    Ok(solution) => solution,
+                    Err(err) => {
+                        println!("Could not parse solution for task {i} in {path}: {err}");
+                        continue;
+                    }
+                };
+                let visibility = score::calc_visibility_fast(&task, &solution);
+                match score::calc(&task, &solution, &visibility) {
+                    Ok(points) => queue.push(contest::QueuedSubmission {
+                        problem: i,
+                        contents,
+                        score: Some(points),
+                    }),
+                    Err(err) => println!("Solution for task {i} is incorrect: {err}"),
+                }
+            }
+
+            let params = contest::SubmitParams {
+                interval: std::time::Duration::from_millis(
+                    *matches.get_one::<u64>("interval-ms").unwrap(),
+                ),
+                ..Default::default()
+            };
+            let queue_path = matches.get_one::<String>("queue").unwrap();
+            if let Err(err) = contest::enqueue(queue_path, queue) {
+                println!("Could not write submission queue: {err}");
+                std::process::exit(1);
+            }
+            let log_path = matches.get_one::<String>("log").unwrap();
+            let results = contest::process_queue(&client, queue_path, log_path, &params);
+            println!(
+                "Submitted {} solutions, {} accepted",
+                results.len(),
+                results.iter().filter(|entry| entry.is_success()).count()
+            );
+        }
+
+        #[cfg(feature = "contest")]
+        Some(("fetch", matches)) => {
+            let range = match matches.get_one::<usize>("id") {
+                Some(&id) => id..=id,
+                None => 1..=TASKS_NUM,
+            };
+            let client =
+                contest::ContestClient::new(matches.get_one::<String>("base-url").unwrap(), None);
+
+            // download everything first, so that failed downloads do not touch existing tasks
+            let tmp_dir = format!("{DATA_DIR}/.fetch");
+            std::fs::create_dir_all(&tmp_dir).expect("Could not create temp dir");
+            let mut downloaded = vec![];
+            for i in range {
+                match contest::fetch_problem(&client, i) {
+                    Ok(data) => {
+                        let tmp_path = format!("{tmp_dir}/problem-{i}.json");
+                        std::fs::write(&tmp_path, &data).expect("Got error when writing to file");
+                        downloaded.push((i, tmp_path, data));
+                    }
+                    Err(err) => println!("Could not fetch task {i}: {err:#}"),
+                }
+            }
+
+            let mut changed = 0;
+            for (i, tmp_path, data) in downloaded {
+                let path = format!("{DATA_DIR}/problem-{i}.json");
+                if std::fs::read_to_string(&path).is_ok_and(|cur| contest::same_task(&cur, &data)) {
+                    continue;
+                }
+                std::fs::rename(&tmp_path, &path).expect("Could not move task");
+                println!("Task {i} was updated");
+                changed += 1;
+            }
+            std::fs::remove_dir_all(&tmp_dir).expect("Could not remove temp dir");
+            println!("{changed} tasks changed");
+        }
+
+        Some(("plan", matches)) => {
+            let range = match matches.get_one::<usize>("id") {
+                Some(&id) => id..=id,
+                None => 1..=TASKS_NUM,
+            };
+            let json = matches.get_flag("json");
+            let history_records = planner::history_records(&history::read(HISTORY_FILE));
+            for i in range.filter(|i| task_exists(*i)) {
+                match planner::plan(&read_task(i), &history_records) {
+                    Ok(plan) if json => {
+                        println!("{}", serde_json::json!({ "problem": i, "plan": plan }));
+                    }
+                    Ok(plan) => {
+                        println!(
+                            "Task {i}: {} with {}",
+                            plan.constructors
+                                .iter()
+                                .map(|c| c.name())
+                                .collect::<Vec<_>>()
+                                .join(" / "),
+                            plan.optimizers
+                                .iter()
+                                .map(|(_, name)| *name)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                        for reason in &plan.reasons {
+                            println!("  {reason}");
+                        }
+                    }
+                    Err(err) => println!("Task {i}: {err}"),
+                }
+            }
+        }
+
+        Some(("analyse", matches)) => {
+            let tasks = match (
+                matches.get_one::<String>("task"),
+                matches.get_one::<usize>("id"),
+            ) {
+                (Some(path), _) => vec![(path.clone(), io::read(path))],
+                (None, Some(&i)) => vec![(format!("problem {i}"), read_task(i))],
+                (None, None) => (1..=TASKS_NUM)
+                    .filter(|i| task_exists(*i))
+                    .map(|i| (format!("problem {i}"), read_task(i)))
+                    .collect(),
+            };
+            let json = matches.get_flag("json");
+            let mut failed = false;
+            for (name, task) in tasks {
+                match analysis::analyse(&task) {
+                    Ok(stats) if json => {
+                        println!("{}", serde_json::json!({ "task": name, "stats": stats }));
+                    }
+                    Ok(stats) => {
+                        println!(
+                            "{name}: {} musicians, {} instruments, {} attendees, {} pillars",
+                            stats.musicians, stats.instruments, stats.attendees, stats.pillars
+                        );
+                        println!(
+                            "  stage: {:.1}% of room, fits {:.2}x musicians",
+                            stats.stage_area_fraction * 100.0,
+                            stats.stage_capacity_ratio
+                        );
+                        println!(
+                            "  negative tastes: {:.1}%, blocked by pillars: {:.1}%",
+                            stats.negative_taste_fraction * 100.0,
+                            stats.pillar_blocked_fraction * 100.0
+                        );
+                        println!(
+                            "  attendees by side (left, right, bottom, top): {:?}",
+                            stats.attendee_sides.map(|f| (f * 100.0).round())
+                        );
+                        println!(
+                            "  attendees by sector: {:?}",
+                            stats
+                                .attendee_sectors
+                                .iter()
+                                .map(|f| (f * 100.0).round())
+                                .collect::<Vec<_>>()
+                        );
+                        for (inst, t) in stats.tastes.iter().enumerate() {
+                            println!(
+                                "  instrument {inst:3} x{:<3} min {:7.0} max {:7.0} mean {:7.1} median {:7.0} negative {:5.1}%",
+                                stats.musicians_per_instrument[inst],
+                                t.min,
+                                t.max,
+                                t.mean,
+                                t.median,
+                                t.negative_fraction * 100.0
+                            );
+                        }
+                        for warning in &stats.warnings {
+                            println!("  warning: {warning}");
+                        }
+                    }
+                    Err(err) => {
+                        failed = true;
+                        if json {
+                            println!(
+                                "{}",
+                                serde_json::json!({ "task": name, "error": err.to_string() })
+                            );
+                        } else {
+                            println!("{name}: invalid task: {err}");
+                        }
+                    }
+                }
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+
+        Some(("generate", matches)) => {
+            let mut params = generator::GeneratorParams::default();
+            if let Some(&seed) = matches.get_one::<u64>("seed") {
+                params.seed = seed;
+            }
+            if let Some(&width) = matches.get_one::<f64>("room-width") {
+                params.room_width = width;
+            }
+            if let Some(&height) = matches.get_one::<f64>("room-height") {
+                params.room_height = height;
+            }
+            if let Some(&width) = matches.get_one::<f64>("stage-width") {
+                params.stage_width = width;
+            }
+            if let Some(&height) = matches.get_one::<f64>("stage-height") {
+                params.stage_height = height;
+            }
+            if let Some(&count) = matches.get_one::<usize>("musicians") {
+                params.musicians = count;
+            }
+            if let Some(&count) = matches.get_one::<usize>("instruments") {
+                params.instruments = count;
+            }
+            if let Some(&count) = matches.get_one::<usize>("attendees") {
+                params.attendees = count;
+            }
+            if let Some(&count) = matches.get_one::<usize>("pillars") {
+                params.pillars = count;
+            }
+            if let Some(distribution) = matches.get_one::<String>("attendee-distribution") {
+                params.attendee_distribution =
+                    distribution.parse().expect("Unknown attendee distribution");
+            }
+            if let Some(distribution) = matches.get_one::<String>("taste-distribution") {
+                params.taste_distribution =
+                    distribution.parse().expect("Unknown taste distribution");
+            }
+
+            let task = match generator::generate(&params) {
+                Ok(task) => task,
+                Err(err) => {
+                    println!("Could not generate task: {err}");
+                    std::process::exit(1);
+                }
+            };
+            let data = serde_json::to_string(&task).expect("Could not serialize task");
+            match matches.get_one::<String>("output") {
+                Some(path) => {
+                    std::fs::write(path, data).expect("Got error when writing to file");
+                    println!(
+                        "Generated task with {} musicians and {} attendees in {path}",
+                        task.musicians.len(),
+                        task.attendees.len()
+                    );
+                }
+                None => println!("{data}"),
+            }
+        }
+
         // Some(("spread_optimize", _matches)) => {
 
         // {
@@ -332,10 +976,6 @@ fn main() {
         // }
         // }
         // }
-        Some(("server", _matches)) => {
-            println!("Starting server on port 8000");
-            http_api::start_server();
-        }
         _ => unreachable!("clap should ensure we don't get here"),
     };
 }
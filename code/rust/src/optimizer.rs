@@ -1,28 +1,46 @@
 use crate::geom::{Point, Segment, Vector};
 use crate::io::{
-    default_volumes_task, MUSICIAN_BLOCK_RADIUS, MUSICIAN_RADIUS, MUSICIAN_RADIUS_SQR,
+    default_volumes_task, MUSICIAN_BLOCK_RADIUS, MUSICIAN_RADIUS, MUSICIAN_RADIUS_SQR, SCORE_CONST,
 };
 use crate::score::{self, calc, calc_ex, calc_visibility, calc_visibility_fast};
-use crate::{genetics, solution};
+use crate::{evolution, lns};
 use crate::{
     io::{Solution, Task},
     score::{attendee_score_without_q, Visibility},
 };
+use float_ord::FloatOrd;
 use itertools::Itertools;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap};
 
 use crate::solution::recalc_volumes;
 use rand::distributions::{Distribution, Uniform};
 use rand::{Rng, SeedableRng};
 use rand_xoshiro::Xoshiro256PlusPlus;
 
-type Optimizer =
+pub type Optimizer =
     fn(&Task, &Solution, &Visibility, &mut Xoshiro256PlusPlus) -> (Solution, Visibility);
-type OptimizerSlice = [(Optimizer, &'static str)];
+pub type OptimizerSlice = [(Optimizer, &'static str)];
 
-const ALL_OPTIMIZERS: &OptimizerSlice = &[
+pub const ALL_OPTIMIZERS: &OptimizerSlice = &[
+    #[cfg(feature = "physics")]
     (rap::rapier_optimizer, "Rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::short_rapier_optimizer, "Short rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::long_rapier_optimizer, "Long rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::bouncy_rapier_optimizer, "Bouncy rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::strong_rapier_optimizer, "Strong rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::gentle_rapier_optimizer, "Gentle rigid body based"),
+    #[cfg(feature = "physics")]
+    (
+        rap::pillar_aware_rapier_optimizer,
+        "Pillar aware rigid body based",
+    ),
     // (rap::silent_musicians_together_rapier_optimizer, "Silent musicians together tigid body based"),
+    #[cfg(feature = "physics")]
     (rap::spread_musicians_rapier_optimizer, "Spread musicians tigid body based"),
     (default_force_based_optimizer, "Force based"),
     (big_step_force_based_optimizer, "Force based with big steps"),
@@ -48,6 +66,13 @@ const ALL_OPTIMIZERS: &OptimizerSlice = &[
         zeros_away_from_audience_optimizer,
         "Wrong taste away from audience",
     ),
+    (gradient_ascent_optimizer, "Gradient ascent"),
+    (
+        evolution::differential_evolution_optimizer,
+        "Differential evolution",
+    ),
+    (lns::lns_optimizer, "Large neighbourhood search"),
+    (silent_blockers_optimizer, "Silent musicians as blockers"),
     (optimize_placements_greedy_opt, "Greedy placement"),
     (random_swap_positions, "Random swap positions"),
     (random_change_positions, "Random change positions"),
@@ -55,8 +80,25 @@ const ALL_OPTIMIZERS: &OptimizerSlice = &[
 ];
 
 const SAFE_OPTIMIZERS: &OptimizerSlice = &[
+    #[cfg(feature = "physics")]
     (rap::rapier_optimizer, "Rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::short_rapier_optimizer, "Short rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::long_rapier_optimizer, "Long rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::bouncy_rapier_optimizer, "Bouncy rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::strong_rapier_optimizer, "Strong rigid body based"),
+    #[cfg(feature = "physics")]
+    (rap::gentle_rapier_optimizer, "Gentle rigid body based"),
+    #[cfg(feature = "physics")]
+    (
+        rap::pillar_aware_rapier_optimizer,
+        "Pillar aware rigid body based",
+    ),
     // (rap::silent_musicians_together_rapier_optimizer, "Silent musicians together tigid body based"),
+    #[cfg(feature = "physics")]
     (rap::spread_musicians_rapier_optimizer, "Spread musicians tigid body based"),
     (default_force_based_optimizer, "Force based"),
     (big_step_force_based_optimizer, "Force based with big steps"),
@@ -82,13 +124,22 @@ const SAFE_OPTIMIZERS: &OptimizerSlice = &[
         zeros_away_from_audience_optimizer,
         "Wrong taste away from audience",
     ),
+    (gradient_ascent_optimizer, "Gradient ascent"),
+    (
+        evolution::differential_evolution_optimizer,
+        "Differential evolution",
+    ),
+    (lns::lns_optimizer, "Large neighbourhood search"),
+    (silent_blockers_optimizer, "Silent musicians as blockers"),
     (optimize_placements_greedy_opt, "Greedy placement"),
     //(optimize_border, "Optimize border"),
 ];
 
-const FINAL_OPTIMIZERS: &OptimizerSlice = &[
+pub const FINAL_OPTIMIZERS: &OptimizerSlice = &[
+    #[cfg(feature = "physics")]
     (rap::rapier_optimizer, "Rigid body based"),
     // (rap::silent_musicians_together_rapier_optimizer, "Silent musicians together tigid body based"),
+    #[cfg(feature = "physics")]
     (rap::spread_musicians_rapier_optimizer, "Spread musicians tigid body based"),
     // (force_random_walk_optimizer, "Force based random walk"),
     (
@@ -222,6 +273,115 @@ fn is_position_possible(
         .all(|(_, other_new_pos)| new_position.dist_sqr(*other_new_pos) >= dist_sqr)
 }
 
+const LEGALIZE_MAX_ROUNDS: usize = 1000;
+// Validation fails on dist < MUSICIAN_RADIUS, keep some slack for float errors
+const LEGALIZE_SLACK: f64 = 1e-6;
+
+// Stage narrower than a musician has no valid positions, use the middle then
+fn clamp_or_middle(value: f64, min: f64, max: f64) -> f64 {
+    if min > max {
+        (min + max) / 2.0
+    } else {
+        value.clamp(min, max)
+    }
+}
+
+fn clamp_to_stage(task: &Task, p: Point) -> Point {
+    Point {
+        x: clamp_or_middle(
+            p.x,
+            task.stage_left() + MUSICIAN_RADIUS,
+            task.stage_right() - MUSICIAN_RADIUS,
+        ),
+        y: clamp_or_middle(
+            p.y,
+            task.stage_bottom() + MUSICIAN_RADIUS,
+            task.stage_top() - MUSICIAN_RADIUS,
+        ),
+    }
+}
+
+// Moves musicians of nearly-valid solution as little as possible so that it passes score::validate.
+// Returns true if any musician was moved. If the solution could not be fixed, it is left untouched
+// and the validation error is returned.
+pub fn legalize_placements(task: &Task, solution: &mut Solution) -> anyhow::Result<bool> {
+    let original = solution.placements.clone();
+    let mut moved = false;
+    for p in solution.placements.iter_mut() {
+        let clamped = clamp_to_stage(task, *p);
+        if clamped.x != p.x || clamped.y != p.y {
+            *p = clamped;
+            moved = true;
+        }
+    }
+
+    let min_dist = MUSICIAN_RADIUS + LEGALIZE_SLACK;
+    let cell = |p: Point| {
+        (
+            (p.x / MUSICIAN_RADIUS).floor() as i64,
+            (p.y / MUSICIAN_RADIUS).floor() as i64,
+        )
+    };
+
+    for _ in 0..LEGALIZE_MAX_ROUNDS {
+        let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (idx, p) in solution.placements.iter().enumerate() {
+            grid.entry(cell(*p)).or_default().push(idx);
+        }
+
+        let mut overlaps = vec![];
+        for (idx, p) in solution.placements.iter().enumerate() {
+            let (cx, cy) = cell(*p);
+            for (dx, dy) in (-1..=1).cartesian_product(-1..=1) {
+                let Some(others) = grid.get(&(cx + dx, cy + dy)) else {
+                    continue;
+                };
+                for &other_idx in others {
+                    if other_idx > idx
+                        && p.dist_sqr(solution.placements[other_idx]) < MUSICIAN_RADIUS_SQR
+                    {
+                        overlaps.push((idx, other_idx));
+                    }
+                }
+            }
+        }
+
+        if overlaps.is_empty() {
+            break;
+        }
+        moved = true;
+
+        for (idx, other_idx) in overlaps {
+            let p = solution.placements[idx];
+            let other = solution.placements[other_idx];
+            let dist = p.dist(other);
+            if dist >= min_dist {
+                // already fixed by previous push in this round
+                continue;
+            }
+            let direction = if dist > 0.0 {
+                (other - p) * (1.0 / dist)
+            } else {
+                // same point, pick direction deterministically
+                let angle = (other_idx as f64) * std::f64::consts::FRAC_PI_4;
+                Vector {
+                    x: angle.cos(),
+                    y: angle.sin(),
+                }
+            };
+            let shift = direction * ((min_dist - dist) / 2.0);
+            solution.placements[idx] = clamp_to_stage(task, p + shift * -1.0);
+            solution.placements[other_idx] = clamp_to_stage(task, other + shift);
+        }
+    }
+
+    if let Err(err) = score::validate(task, solution) {
+        solution.placements = original;
+        return Err(err);
+    }
+    Ok(moved)
+}
+
 fn run_force_based_step(
     task: &Task,
     start_solution: &Solution,
@@ -330,6 +490,46 @@ pub fn attract_musicians_to_attendees_force_collector(
         .sum::<Vector>()
 }
 
+// Pushes musician sideways out of pillar shadows, towards attendees who would like to hear them
+pub fn pillar_shadow_force_collector(
+    task: &Task,
+    visibility: &Visibility,
+    pos_index: usize,
+    old_position: Point,
+) -> Vector {
+    let instrument = task.musicians[pos_index];
+    task.attendees
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !visibility.is_visible(*index, pos_index))
+        .filter(|(_, attendee)| attendee.tastes[instrument] > 0.0)
+        .filter_map(|(_, attendee)| {
+            let segment = Segment {
+                from: old_position,
+                to: attendee.coord(),
+            };
+            let pillar = task
+                .pillars
+                .iter()
+                .find(|p| segment.dist(p.point()) < p.radius)?;
+
+            let to_attendee = attendee.coord() - old_position;
+            let normal = Vector {
+                x: -to_attendee.y,
+                y: to_attendee.x,
+            };
+            // move to the side of the pillar which is closer to us
+            let side = if (old_position - pillar.point()).dot(normal) >= 0.0 {
+                1.0
+            } else {
+                -1.0
+            };
+            let force = attendee_score_without_q(attendee, instrument, old_position) as f64;
+            Some(normal * (force * side))
+        })
+        .sum::<Vector>()
+}
+
 pub fn force_based_optimizer(
     task: &Task,
     initial_solution: &Solution,
@@ -857,6 +1057,112 @@ pub fn zeros_away_from_audience_optimizer(
     )
 }
 
+const BLOCKER_HARMFUL_PAIRS: usize = 50;
+const BLOCKER_PAIRS_BUDGET: usize = 100_000_000;
+const BLOCKER_MAX_EVALUATIONS: usize = 40;
+// Blocker closer to the attendee hides smaller angle, so we start from the stage edge
+const BLOCKER_EDGE_FRACTIONS: [f64; 4] = [1.0, 0.75, 0.5, 0.25];
+
+// Silent musicians still block sound. Put them between loud musicians and attendees who hate them.
+pub fn silent_blockers_optimizer(
+    task: &Task,
+    initial_solution: &Solution,
+    visibility: &Visibility,
+    _rng: &mut Xoshiro256PlusPlus,
+) -> (Solution, Visibility) {
+    let mut best = initial_solution.clone();
+    best.volumes = default_volumes_task(task);
+    recalc_volumes(task, &mut best, visibility);
+    let mut best_visibility = visibility.clone();
+    let mut best_score = match calc(task, &best, &best_visibility) {
+        Ok(score) => score,
+        Err(_) => return (initial_solution.clone(), visibility.clone()),
+    };
+
+    let is_silent = |solution: &Solution, idx: usize| solution.volumes[idx] == 0.0;
+    if !(0..best.placements.len()).any(|idx| is_silent(&best, idx)) {
+        return (best, best_visibility);
+    }
+
+    let mut harmful_pairs = vec![];
+    for (att_idx, att) in task.attendees.iter().enumerate() {
+        for mus_idx in best_visibility.for_attendee(att_idx) {
+            if is_silent(&best, mus_idx) {
+                continue;
+            }
+            let score =
+                attendee_score_without_q(att, task.musicians[mus_idx], best.placements[mus_idx]);
+            if score < 0 {
+                harmful_pairs.push((score, mus_idx, att_idx));
+            }
+        }
+    }
+    harmful_pairs.sort();
+    harmful_pairs.truncate(BLOCKER_HARMFUL_PAIRS);
+
+    let pairs = task.musicians.len() * (task.musicians.len() + task.attendees.len());
+    let mut evaluations = (BLOCKER_PAIRS_BUDGET / pairs.max(1)).clamp(1, BLOCKER_MAX_EVALUATIONS);
+
+    'pairs: for (_, mus_idx, att_idx) in harmful_pairs {
+        let from = best.placements[mus_idx];
+        let to_attendee = task.attendees[att_idx].coord() - from;
+        let direction = to_attendee * (1.0 / to_attendee.norm());
+
+        // distance to the stage edge in attendee direction
+        let to_edge_x = if direction.x > 0.0 {
+            (task.stage_right() - MUSICIAN_RADIUS - from.x) / direction.x
+        } else if direction.x < 0.0 {
+            (task.stage_left() + MUSICIAN_RADIUS - from.x) / direction.x
+        } else {
+            f64::INFINITY
+        };
+        let to_edge_y = if direction.y > 0.0 {
+            (task.stage_top() - MUSICIAN_RADIUS - from.y) / direction.y
+        } else if direction.y < 0.0 {
+            (task.stage_bottom() + MUSICIAN_RADIUS - from.y) / direction.y
+        } else {
+            f64::INFINITY
+        };
+        let to_edge = to_edge_x.min(to_edge_y);
+
+        for fraction in BLOCKER_EDGE_FRACTIONS {
+            let distance = to_edge * fraction;
+            if distance < MUSICIAN_RADIUS {
+                break;
+            }
+            let blocker_pos = from + direction * distance;
+
+            let Some(blocker_idx) = (0..best.placements.len())
+                .filter(|idx| is_silent(&best, *idx))
+                .filter(|idx| is_position_possible(&best, *idx, blocker_pos, MUSICIAN_RADIUS_SQR))
+                .min_by_key(|idx| FloatOrd(best.placements[*idx].dist_sqr(blocker_pos)))
+            else {
+                continue;
+            };
+
+            let mut solution = best.clone();
+            solution.placements[blocker_idx] = blocker_pos;
+            solution.volumes = default_volumes_task(task);
+            let visibility = calc_visibility_fast(task, &solution);
+            recalc_volumes(task, &mut solution, &visibility);
+            if let Ok(score) = calc(task, &solution, &visibility) {
+                if score > best_score {
+                    best = solution;
+                    best_visibility = visibility;
+                    best_score = score;
+                }
+            }
+
+            evaluations -= 1;
+            if evaluations == 0 {
+                break 'pairs;
+            }
+        }
+    }
+
+    (best, best_visibility)
+}
+
 pub fn default_force_based_optimizer(
     task: &Task,
     initial_solution: &Solution,
@@ -931,6 +1237,160 @@ pub fn big_gap_big_step_force_based_optimizer(
     )
 }
 
+const GRADIENT_STEPS: usize = 200;
+const GRADIENT_REFRESH_VISIBILITY_RATE: usize = 10;
+const GRADIENT_INITIAL_STEP: f64 = MUSICIAN_RADIUS;
+const GRADIENT_MIN_STEP: f64 = 1e-3;
+
+// Impact of every musician on visible attendees, without q and volume
+fn musician_impacts(task: &Task, solution: &Solution, visibility: &Visibility) -> Vec<f64> {
+    solution
+        .placements
+        .iter()
+        .enumerate()
+        .map(|(mus_idx, mus_pos)| {
+            let instrument = task.musicians[mus_idx];
+            task.attendees
+                .iter()
+                .enumerate()
+                .filter(|(att_idx, _)| visibility.is_visible(*att_idx, mus_idx))
+                .map(|(_, att)| {
+                    SCORE_CONST * att.tastes[instrument] / mus_pos.dist_sqr(att.coord())
+                })
+                .sum()
+        })
+        .collect()
+}
+
+// Score for fixed visibility without rounding. Musicians with negative impact are considered silent
+// just like recalc_volumes does.
+pub fn smooth_score(task: &Task, solution: &Solution, visibility: &Visibility) -> f64 {
+    let musician2q = score::calc_musician2q(task, solution);
+    musician_impacts(task, solution, visibility)
+        .into_iter()
+        .zip(musician2q)
+        .map(|(impact, q)| (impact * q).max(0.0))
+        .sum()
+}
+
+// Exact gradient of smooth_score with respect to every musician position
+pub fn score_gradient(task: &Task, solution: &Solution, visibility: &Visibility) -> Vec<Vector> {
+    let impacts = musician_impacts(task, solution, visibility);
+    let musician2q = score::calc_musician2q(task, solution);
+    let active = impacts
+        .iter()
+        .zip(musician2q.iter())
+        .map(|(impact, q)| impact * q > 0.0)
+        .collect::<Vec<_>>();
+
+    solution
+        .placements
+        .iter()
+        .enumerate()
+        .map(|(mus_idx, mus_pos)| {
+            let instrument = task.musicians[mus_idx];
+            let mut gradient = Vector { x: 0.0, y: 0.0 };
+
+            if active[mus_idx] {
+                // d(1/d^2)/dp = -2 (p - a) / d^4
+                let impact_gradient = task
+                    .attendees
+                    .iter()
+                    .enumerate()
+                    .filter(|(att_idx, _)| visibility.is_visible(*att_idx, mus_idx))
+                    .map(|(_, att)| {
+                        let v = *mus_pos - att.coord();
+                        let d2 = v.norm2();
+                        v * (-2.0 * SCORE_CONST * att.tastes[instrument] / (d2 * d2))
+                    })
+                    .sum::<Vector>();
+                gradient = gradient + impact_gradient * musician2q[mus_idx];
+            }
+
+            if !task.pillars.is_empty() {
+                // q(k) = 1 + sum 1/d(k, j), so the term changes both q(k) and q(j)
+                for (other_idx, other_pos) in solution.placements.iter().enumerate() {
+                    if other_idx == mus_idx || task.musicians[other_idx] != instrument {
+                        continue;
+                    }
+                    let weight = if active[mus_idx] {
+                        impacts[mus_idx]
+                    } else {
+                        0.0
+                    } + if active[other_idx] {
+                        impacts[other_idx]
+                    } else {
+                        0.0
+                    };
+                    let v = *mus_pos - *other_pos;
+                    let d = v.norm();
+                    gradient = gradient + v * (-weight / (d * d * d));
+                }
+            }
+
+            gradient
+        })
+        .collect()
+}
+
+pub fn gradient_ascent_optimizer(
+    task: &Task,
+    initial_solution: &Solution,
+    visibility: &Visibility,
+    _rng: &mut Xoshiro256PlusPlus,
+) -> (Solution, Visibility) {
+    let mut solution = initial_solution.clone();
+    let mut visibility = visibility.clone();
+    let mut current = smooth_score(task, &solution, &visibility);
+    let mut step_size = GRADIENT_INITIAL_STEP;
+
+    for step in 0..GRADIENT_STEPS {
+        if step_size < GRADIENT_MIN_STEP {
+            break;
+        }
+
+        let gradient = score_gradient(task, &solution, &visibility);
+        let max_norm = gradient
+            .iter()
+            .map(|g| g.norm())
+            .max_by(|a, b| a.partial_cmp(b).unwrap())
+            .unwrap_or(0.0);
+        if max_norm == 0.0 {
+            break;
+        }
+
+        // backtracking line search along projected gradient
+        loop {
+            let mut candidate = solution.clone();
+            for (p, g) in candidate.placements.iter_mut().zip(gradient.iter()) {
+                *p = *p + *g * (step_size / max_norm);
+            }
+            let legal = legalize_placements(task, &mut candidate).is_ok();
+
+            let candidate_score = smooth_score(task, &candidate, &visibility);
+            if legal && candidate_score > current {
+                solution = candidate;
+                current = candidate_score;
+                step_size *= 1.5;
+                break;
+            }
+
+            step_size /= 2.0;
+            if step_size < GRADIENT_MIN_STEP {
+                break;
+            }
+        }
+
+        if (step + 1) % GRADIENT_REFRESH_VISIBILITY_RATE == 0 {
+            visibility = calc_visibility_fast(task, &solution);
+            current = smooth_score(task, &solution, &visibility);
+        }
+    }
+
+    let visibility = calc_visibility_fast(task, &solution);
+    (solution, visibility)
+}
+
 pub fn optimize_single_musicians(
     task: &Task,
     initial_solution: &Solution,
@@ -1286,13 +1746,26 @@ pub fn optimize_do_talogo(
     initial_solution: &Solution,
     visibility: Visibility,
 ) -> (Solution, Visibility) {
+    let (solution, visibility, _) =
+        optimize_do_talogo_with_chains(task, initial_solution, visibility, FINAL_OPTIMIZERS);
+    (solution, visibility)
+}
+
+// Same as optimize_do_talogo with custom optimizers, also returns chains which improved the score
+pub fn optimize_do_talogo_with_chains(
+    task: &Task,
+    initial_solution: &Solution,
+    visibility: Visibility,
+    optimizers: &OptimizerSlice,
+) -> (Solution, Visibility, Vec<String>) {
     let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
 
     let mut best_solution = initial_solution.clone();
     let mut best_visibility = visibility.clone();
+    let mut best_chains = vec![];
     let mut max_score = match calc(task, initial_solution, &visibility) {
         Ok(res) => res,
-        _ => return (best_solution, visibility),
+        _ => return (best_solution, visibility, best_chains),
     };
 
     let mut score_changed = true;
@@ -1325,8 +1798,17 @@ pub fn optimize_do_talogo(
                     // println!("Trying {name}");
 
                     try_solution.volumes = default_volumes_task(task);
-                    let (mut solution, visibility) =
+                    let (mut solution, mut visibility) =
                         optimize(&task, &try_solution, &try_visibility, &mut rng);
+                    match legalize_placements(task, &mut solution) {
+                        Ok(true) => visibility = calc_visibility_fast(task, &solution),
+                        Ok(false) => {}
+                        Err(err) => {
+                            // skip this step, the rest of the chain continues from previous solution
+                            println!("{name} produced invalid solution: {err}");
+                            continue;
+                        }
+                    }
                     recalc_volumes(task, &mut solution, &visibility);
 
                     try_solution = solution;
@@ -1354,6 +1836,7 @@ pub fn optimize_do_talogo(
                             max_score = points;
                             best_solution = try_solution;
                             best_visibility = try_visibility;
+                            best_chains.push(chain_names.iter().join(" -> "));
                             score_changed = true;
                         }
                     }
@@ -1366,35 +1849,258 @@ pub fn optimize_do_talogo(
 
         // run(ALL_OPTIMIZERS);
         // run(SAFE_OPTIMIZERS);
-        run(FINAL_OPTIMIZERS);
+        run(optimizers);
     }
 
-    (best_solution, best_visibility)
+    (best_solution, best_visibility, best_chains)
 }
 
-mod rap {
+#[cfg(feature = "physics")]
+pub mod rap {
     use rand_xoshiro::Xoshiro256PlusPlus;
     use rapier2d::prelude::*;
+    use std::collections::{BTreeMap, HashMap};
 
-    use crate::io::{Solution, Task, MUSICIAN_RADIUS};
-    use crate::optimizer::attract_musicians_to_attendees_force_collector;
-    use crate::score::{calc_visibility, calc_visibility_fast, Visibility};
+    use crate::geom::{Point, Vector};
+    use crate::io::{default_volumes_task, Solution, Task, MUSICIAN_RADIUS};
+    use crate::optimizer::{
+        attract_musicians_to_attendees_force_collector, legalize_placements,
+        pillar_shadow_force_collector, Optimizer,
+    };
+    use crate::score::{calc, calc_visibility, calc_visibility_fast, Visibility};
     use crate::solution::recalc_volumes;
 
-    // const MUSICIAN_RESTITUTION: f32 = 0.7;
-    const MUSICIAN_RESTITUTION: f32 = 0.1;
-    const MAX_PENETRATION: f32 = 1e-4;
-    const MUSICIAN_BALL_RADIUS: f32 = (MUSICIAN_RADIUS / 2.0) as f32 + 4.0 * MAX_PENETRATION;
-    const STEPS: usize = 1000;
-    const REFRESH_VISIBILITY_RATE: usize = 10;
-    const RECALC_FORCES_RATE: usize = 10;
-    const FORCE_MULTPLIER: f64 = 10.0;
+    #[derive(Clone, Debug)]
+    pub struct RapierParams {
+        pub musician_restitution: f32,
+        pub max_penetration: f32,
+        pub steps: usize,
+        pub refresh_visibility_rate: usize,
+        pub recalc_forces_rate: usize,
+        pub force_multiplier: f64,
+        pub stabilize_steps: usize,
+        // pillar shadows of this many best attendees per pillar are sensors pushing musicians out
+        pub shadow_sensors: usize,
+    }
+
+    const PILLAR_SHADOW_SENSORS: usize = 5;
+
+    const DEFAULT_PARAMS: RapierParams = RapierParams {
+        musician_restitution: 0.1,
+        max_penetration: 1e-4,
+        steps: 1000,
+        refresh_visibility_rate: 10,
+        recalc_forces_rate: 10,
+        force_multiplier: 10.0,
+        stabilize_steps: 100,
+        shadow_sensors: 0,
+    };
+    const SHORT_PARAMS: RapierParams = RapierParams {
+        steps: 300,
+        ..DEFAULT_PARAMS
+    };
+    const LONG_PARAMS: RapierParams = RapierParams {
+        steps: 3000,
+        refresh_visibility_rate: 30,
+        ..DEFAULT_PARAMS
+    };
+    const BOUNCY_PARAMS: RapierParams = RapierParams {
+        musician_restitution: 0.7,
+        ..DEFAULT_PARAMS
+    };
+    const STRONG_PARAMS: RapierParams = RapierParams {
+        force_multiplier: 50.0,
+        stabilize_steps: 300,
+        ..DEFAULT_PARAMS
+    };
+    const GENTLE_PARAMS: RapierParams = RapierParams {
+        force_multiplier: 2.0,
+        recalc_forces_rate: 2,
+        refresh_visibility_rate: 2,
+        steps: 500,
+        ..DEFAULT_PARAMS
+    };
+
+    impl Default for RapierParams {
+        fn default() -> Self {
+            DEFAULT_PARAMS
+        }
+    }
+
+    impl RapierParams {
+        fn musician_ball_radius(&self) -> f32 {
+            (MUSICIAN_RADIUS / 2.0) as f32 + 4.0 * self.max_penetration
+        }
+    }
+
+    // Preset name, its params and optimizer registered for it
+    pub const PRESETS: &[(&str, RapierParams, (Optimizer, &str))] = &[
+        (
+            "default",
+            DEFAULT_PARAMS,
+            (rapier_optimizer, "Rigid body based"),
+        ),
+        (
+            "short",
+            SHORT_PARAMS,
+            (short_rapier_optimizer, "Short rigid body based"),
+        ),
+        (
+            "long",
+            LONG_PARAMS,
+            (long_rapier_optimizer, "Long rigid body based"),
+        ),
+        (
+            "bouncy",
+            BOUNCY_PARAMS,
+            (bouncy_rapier_optimizer, "Bouncy rigid body based"),
+        ),
+        (
+            "strong",
+            STRONG_PARAMS,
+            (strong_rapier_optimizer, "Strong rigid body based"),
+        ),
+        (
+            "gentle",
+            GENTLE_PARAMS,
+            (gentle_rapier_optimizer, "Gentle rigid body based"),
+        ),
+    ];
+
+    // problem id -> preset name -> score
+    pub type Tuning = BTreeMap<usize, BTreeMap<String, i64>>;
+
+    // Missing or broken file means nothing is tuned yet
+    pub fn read_tuning(path: &str) -> Tuning {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn write_tuning(path: &str, tuning: &Tuning) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(tuning)?)?;
+        Ok(())
+    }
+
+    // Preset with the best recorded score for the problem
+    pub fn best_preset(
+        tuning: &Tuning,
+        problem: usize,
+    ) -> Option<&'static (&'static str, RapierParams, (Optimizer, &'static str))> {
+        let scores = tuning.get(&problem)?;
+        PRESETS
+            .iter()
+            .filter(|(name, _, _)| scores.contains_key(*name))
+            .max_by_key(|(name, _, _)| scores[*name])
+    }
+
+    // Replaces default rigid body optimizer in the portfolio with the best tuned preset.
+    // Returns name of the preset if it was applied
+    pub fn apply_tuning(
+        optimizers: &mut [(Optimizer, &'static str)],
+        tuning: &Tuning,
+        problem: usize,
+    ) -> Option<&'static str> {
+        let (name, _, tuned) = best_preset(tuning, problem)?;
+        let (_, default_name) = PRESETS[0].2;
+        let slot = optimizers.iter_mut().find(|(_, n)| *n == default_name)?;
+        *slot = *tuned;
+        Some(name)
+    }
+
+    // Runs every preset of attraction-based optimizer and returns their scores, best first
+    pub fn tune(
+        task: &Task,
+        solution: &Solution,
+        visibility: &Visibility,
+        rng: &mut Xoshiro256PlusPlus,
+    ) -> Vec<(&'static str, i64, Solution, Visibility)> {
+        let mut results = PRESETS
+            .iter()
+            .map(|(name, params, _)| {
+                let mut try_solution = solution.clone();
+                try_solution.volumes = default_volumes_task(task);
+                let (mut result, mut visibility) =
+                    rapier_optimizer_with_params(task, &try_solution, visibility, rng, params);
+                let points = match legalize_placements(task, &mut result) {
+                    Ok(moved) => {
+                        if moved {
+                            visibility = calc_visibility_fast(task, &result);
+                        }
+                        recalc_volumes(task, &mut result, &visibility);
+                        calc(task, &result, &visibility).unwrap_or(i64::MIN)
+                    }
+                    Err(_) => i64::MIN,
+                };
+                println!("Rapier preset {name} got {points} points");
+                (*name, points, result, visibility)
+            })
+            .collect::<Vec<_>>();
+        results.sort_by_key(|(_, points, _, _)| -points);
+        results
+    }
 
     pub fn rapier_optimizer(
         task: &Task,
         solution: &Solution,
         visibility: &Visibility,
         rng: &mut Xoshiro256PlusPlus,
+    ) -> (Solution, Visibility) {
+        rapier_optimizer_with_params(task, solution, visibility, rng, &DEFAULT_PARAMS)
+    }
+
+    pub fn short_rapier_optimizer(
+        task: &Task,
+        solution: &Solution,
+        visibility: &Visibility,
+        rng: &mut Xoshiro256PlusPlus,
+    ) -> (Solution, Visibility) {
+        rapier_optimizer_with_params(task, solution, visibility, rng, &SHORT_PARAMS)
+    }
+
+    pub fn long_rapier_optimizer(
+        task: &Task,
+        solution: &Solution,
+        visibility: &Visibility,
+        rng: &mut Xoshiro256PlusPlus,
+    ) -> (Solution, Visibility) {
+        rapier_optimizer_with_params(task, solution, visibility, rng, &LONG_PARAMS)
+    }
+
+    pub fn bouncy_rapier_optimizer(
+        task: &Task,
+        solution: &Solution,
+        visibility: &Visibility,
+        rng: &mut Xoshiro256PlusPlus,
+    ) -> (Solution, Visibility) {
+        rapier_optimizer_with_params(task, solution, visibility, rng, &BOUNCY_PARAMS)
+    }
+
+    pub fn strong_rapier_optimizer(
+        task: &Task,
+        solution: &Solution,
+        visibility: &Visibility,
+        rng: &mut Xoshiro256PlusPlus,
+    ) -> (Solution, Visibility) {
+        rapier_optimizer_with_params(task, solution, visibility, rng, &STRONG_PARAMS)
+    }
+
+    pub fn gentle_rapier_optimizer(
+        task: &Task,
+        solution: &Solution,
+        visibility: &Visibility,
+        rng: &mut Xoshiro256PlusPlus,
+    ) -> (Solution, Visibility) {
+        rapier_optimizer_with_params(task, solution, visibility, rng, &GENTLE_PARAMS)
+    }
+
+    pub fn rapier_optimizer_with_params(
+        task: &Task,
+        solution: &Solution,
+        visibility: &Visibility,
+        rng: &mut Xoshiro256PlusPlus,
+        params: &RapierParams,
     ) -> (Solution, Visibility) {
         let force_collector =
             |task: &Task,
@@ -1411,13 +2117,7 @@ mod rap {
                 )
             };
 
-        rapier_optimizer_base(
-            task,
-            solution,
-            visibility,
-            rng,
-            force_collector,
-        )
+        rapier_optimizer_base(task, solution, visibility, rng, params, force_collector)
     }
 
     pub fn silent_musicians_together_rapier_optimizer(
@@ -1465,6 +2165,7 @@ mod rap {
             &solution_with_volume,
             visibility,
             rng,
+            &Default::default(),
             force_collector,
         )
     }
@@ -1497,15 +2198,86 @@ mod rap {
             solution,
             visibility,
             rng,
+            &Default::default(),
             force_collector,
         )
     }
 
+    pub fn pillar_aware_rapier_optimizer(
+        task: &Task,
+        solution: &Solution,
+        visibility: &Visibility,
+        rng: &mut Xoshiro256PlusPlus,
+    ) -> (Solution, Visibility) {
+        if task.pillars.is_empty() {
+            // nothing casts shadows, plain rapier optimizer does the rest
+            return (solution.clone(), visibility.clone());
+        }
+
+        let force_collector = |task: &Task,
+                               _start_solution: &Solution,
+                               visibility: &Visibility,
+                               pos_index: usize,
+                               old_position: crate::geom::Point,
+                               _rng: &mut Xoshiro256PlusPlus| {
+            attract_musicians_to_attendees_force_collector(
+                task,
+                visibility,
+                pos_index,
+                old_position,
+            ) + pillar_shadow_force_collector(task, visibility, pos_index, old_position)
+        };
+
+        let params = RapierParams {
+            shadow_sensors: PILLAR_SHADOW_SENSORS,
+            ..Default::default()
+        };
+        rapier_optimizer_base(task, solution, visibility, rng, &params, force_collector)
+    }
+
+    // Shadow of the pillar as seen by the attendee, cut at the far end of the stage.
+    // Returns polygon and the shadow axis as (pillar center, direction from attendee)
+    pub(crate) fn pillar_shadow(
+        task: &Task,
+        attendee: Point,
+        pillar: &crate::io::Pillar,
+    ) -> Option<(Vec<Point>, Point, Vector)> {
+        let center = pillar.point();
+        let dist = attendee.dist(center);
+        if dist <= pillar.radius {
+            return None;
+        }
+        let dir = (center - attendee) * (1.0 / dist);
+        let normal = Vector {
+            x: -dir.y,
+            y: dir.x,
+        };
+        let length = [
+            (task.stage_left(), task.stage_bottom()),
+            (task.stage_left(), task.stage_top()),
+            (task.stage_right(), task.stage_bottom()),
+            (task.stage_right(), task.stage_top()),
+        ]
+        .into_iter()
+        .map(|(x, y)| attendee.dist(Point { x, y }))
+        .fold(0.0, f64::max);
+        if length <= dist {
+            return None;
+        }
+        let near = [
+            center + normal * pillar.radius,
+            center + normal * -pillar.radius,
+        ];
+        let far = near.map(|p| attendee + (p - attendee) * (length / dist));
+        Some((vec![near[0], near[1], far[1], far[0]], center, dir))
+    }
+
     pub fn rapier_optimizer_base(
         task: &Task,
         solution: &Solution,
         visibility: &Visibility,
         rng: &mut Xoshiro256PlusPlus,
+        params: &RapierParams,
         mut force_collector: impl FnMut(
             &Task,
             &Solution,
@@ -1549,19 +2321,72 @@ mod rap {
             collider_set.insert(collider);
         }
 
+        /* Create the pillars touching the stage. */
+        for pillar in task.pillars.iter() {
+            let (cx, cy) = pillar.center;
+            let nearest_x = cx.clamp(task.stage_left(), task.stage_right());
+            let nearest_y = cy.clamp(task.stage_bottom(), task.stage_top());
+            let stage_dist_sqr =
+                (cx - nearest_x) * (cx - nearest_x) + (cy - nearest_y) * (cy - nearest_y);
+            if stage_dist_sqr > pillar.radius * pillar.radius {
+                continue;
+            }
+
+            let collider = ColliderBuilder::ball(pillar.radius as f32)
+                .translation(vector![cx as f32, cy as f32])
+                .build();
+            collider_set.insert(collider);
+        }
+
+        /* Create sensors in pillar shadows of the most valuable attendees. */
+        let mut shadow_sensors = vec![];
+        if params.shadow_sensors > 0 {
+            let mut best_attendees = task.attendees.iter().collect::<Vec<_>>();
+            best_attendees.sort_by(|a, b| {
+                let value = |a: &crate::io::Attendee| -> f64 {
+                    a.tastes.iter().filter(|t| **t > 0.0).sum()
+                };
+                value(b).partial_cmp(&value(a)).unwrap()
+            });
+            for pillar in task.pillars.iter() {
+                for attendee in best_attendees.iter().take(params.shadow_sensors) {
+                    let Some((polygon, center, dir)) =
+                        pillar_shadow(task, attendee.coord(), pillar)
+                    else {
+                        continue;
+                    };
+                    let points = polygon
+                        .iter()
+                        .map(|p| point![p.x as f32, p.y as f32])
+                        .collect::<Vec<_>>();
+                    if let Some(builder) = ColliderBuilder::convex_hull(&points) {
+                        let handle = collider_set.insert(builder.sensor(true).build());
+                        shadow_sensors.push((handle, center, dir));
+                    }
+                }
+            }
+        }
+
         /* Create the musicians balls. */
+        let mut musician_by_collider = HashMap::new();
         let musician_body_handles = solution
             .placements
             .iter()
-            .map(|pos| {
+            .enumerate()
+            .map(|(mus_idx, pos)| {
                 let rigid_body = RigidBodyBuilder::dynamic()
                     .translation(vector![pos.x as f32, pos.y as f32])
                     .build();
-                let collider = ColliderBuilder::ball(MUSICIAN_BALL_RADIUS)
-                    .restitution(MUSICIAN_RESTITUTION)
+                let collider = ColliderBuilder::ball(params.musician_ball_radius())
+                    .restitution(params.musician_restitution)
                     .build();
                 let ball_body_handle = rigid_body_set.insert(rigid_body);
-                collider_set.insert_with_parent(collider, ball_body_handle, &mut rigid_body_set);
+                let collider_handle = collider_set.insert_with_parent(
+                    collider,
+                    ball_body_handle,
+                    &mut rigid_body_set,
+                );
+                musician_by_collider.insert(collider_handle, mus_idx);
                 ball_body_handle
             })
             .collect::<Vec<_>>();
@@ -1569,7 +2394,7 @@ mod rap {
         /* Create other structures necessary for the simulation. */
         let gravity = vector![0.0, 0.0];
         let integration_parameters = IntegrationParameters {
-            allowed_linear_error: MAX_PENETRATION,
+            allowed_linear_error: params.max_penetration,
             // max_stabilization_iterations: 10,
             ..IntegrationParameters::default()
         };
@@ -1615,8 +2440,8 @@ mod rap {
         let mut result = solution.clone();
 
         /* Run the game loop, stepping the simulation once per frame. */
-        for step in 0..STEPS {
-            if step % RECALC_FORCES_RATE == 0 {
+        for step in 0..params.steps {
+            if step % params.recalc_forces_rate == 0 {
                 let mut forces = result
                     .placements
                     .iter()
@@ -1642,9 +2467,41 @@ mod rap {
                     .max_by(|a, b| a.partial_cmp(b).unwrap())
                     .unwrap();
 
+                // musicians inside of shadow sensors are pushed away from the shadow axis
+                let mut shadow_push = vec![Vector { x: 0.0, y: 0.0 }; result.placements.len()];
+                for (sensor, center, dir) in &shadow_sensors {
+                    for (h1, h2, intersecting) in narrow_phase.intersections_with(*sensor) {
+                        let other = if h1 == *sensor { h2 } else { h1 };
+                        let Some(&mus_idx) = musician_by_collider.get(&other) else {
+                            continue;
+                        };
+                        if !intersecting {
+                            continue;
+                        }
+                        let normal = Vector {
+                            x: -dir.y,
+                            y: dir.x,
+                        };
+                        let side = if (result.placements[mus_idx] - *center).dot(normal) >= 0.0 {
+                            1.0
+                        } else {
+                            -1.0
+                        };
+                        shadow_push[mus_idx] = shadow_push[mus_idx] + normal * side;
+                    }
+                }
+
                 forces
                     .into_iter()
-                    .map(|(i, f)| (i, f * (1.0 / max_norm * FORCE_MULTPLIER)))
+                    .map(|(i, f)| (i, f * (1.0 / max_norm * params.force_multiplier)))
+                    .map(|(i, f)| {
+                        let push = shadow_push[i];
+                        if push.norm() > 0.0 {
+                            (i, f + push * (params.force_multiplier / push.norm()))
+                        } else {
+                            (i, f)
+                        }
+                    })
                     .for_each(|(pos_index, force)| {
                         let body_handle = musician_body_handles[pos_index];
                         let body = &mut rigid_body_set[body_handle];
@@ -1672,7 +2529,7 @@ mod rap {
                 &event_handler,
             );
 
-            if (step + 1) % REFRESH_VISIBILITY_RATE == 0 {
+            if (step + 1) % params.refresh_visibility_rate == 0 {
                 result = collect_solution(&rigid_body_set, &musician_body_handles, solution);
                 // visibility = calc_visibility_fast(&task, &result);
                 visibility = calc_visibility(&task, &result);
@@ -1685,8 +2542,7 @@ mod rap {
                 body.reset_forces(true);
             }
 
-            const STABILIZE_STEPS: usize = 100;
-            for step in 0..STABILIZE_STEPS {
+            for _ in 0..params.stabilize_steps {
                 physics_pipeline.step(
                     &gravity,
                     &integration_parameters,
@@ -1711,3 +2567,195 @@ mod rap {
         (result, visibility)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::legalize_placements;
+    use crate::geom::Point;
+    use crate::io::{Attendee, Solution, Task};
+    use crate::score::validate;
+
+    #[test]
+    fn test_legalize_placements() {
+        let task = Task {
+            room_width: 200.0,
+            room_height: 200.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 60.0,
+            stage_height: 60.0,
+            stage_bottom_left: (50.0, 50.0),
+            musicians: vec![0, 0, 0, 0],
+            attendees: vec![Attendee {
+                x: 10.0,
+                y: 10.0,
+                tastes: vec![1.0],
+            }],
+            pillars: vec![],
+        };
+        let mut solution = Solution {
+            placements: vec![
+                Point { x: 70.0, y: 70.0 },
+                Point { x: 70.0, y: 70.0 },
+                Point { x: 75.0, y: 71.0 },
+                Point { x: 0.0, y: 100.0 },
+            ],
+            volumes: vec![1.0; 4],
+        };
+        assert!(validate(&task, &solution).is_err());
+
+        assert!(legalize_placements(&task, &mut solution).unwrap());
+        assert!(validate(&task, &solution).is_ok());
+        assert!(!legalize_placements(&task, &mut solution).unwrap());
+    }
+
+    #[test]
+    fn test_legalize_placements_reports_impossible() {
+        // stage is narrower than a musician
+        let task = Task {
+            room_width: 200.0,
+            room_height: 200.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 15.0,
+            stage_height: 60.0,
+            stage_bottom_left: (50.0, 50.0),
+            musicians: vec![0, 0],
+            attendees: vec![Attendee {
+                x: 10.0,
+                y: 10.0,
+                tastes: vec![1.0],
+            }],
+            pillars: vec![],
+        };
+        let mut solution = Solution {
+            placements: vec![Point { x: 57.0, y: 70.0 }, Point { x: 57.0, y: 75.0 }],
+            volumes: vec![1.0; 2],
+        };
+        let original = solution.placements.clone();
+        assert!(legalize_placements(&task, &mut solution).is_err());
+        assert_eq!(solution.placements[0].y, original[0].y);
+        assert_eq!(solution.placements[1].y, original[1].y);
+
+        // too many musicians for the stage
+        let task = Task {
+            stage_width: 20.0,
+            stage_height: 20.0,
+            musicians: vec![0, 0, 0],
+            ..task
+        };
+        let mut solution = Solution {
+            placements: vec![Point { x: 60.0, y: 60.0 }; 3],
+            volumes: vec![1.0; 3],
+        };
+        assert!(legalize_placements(&task, &mut solution).is_err());
+    }
+
+    #[cfg(feature = "physics")]
+    #[test]
+    fn test_apply_rapier_tuning() {
+        use super::rap::{apply_tuning, Tuning, PRESETS};
+        use super::FINAL_OPTIMIZERS;
+
+        let mut tuning = Tuning::new();
+        tuning.insert(
+            7,
+            [("default", 10), ("long", 30), ("gentle", 20)]
+                .into_iter()
+                .map(|(name, score)| (name.to_string(), score))
+                .collect(),
+        );
+
+        let mut optimizers = FINAL_OPTIMIZERS.to_vec();
+        assert_eq!(apply_tuning(&mut optimizers, &tuning, 8), None);
+        assert_eq!(apply_tuning(&mut optimizers, &tuning, 7), Some("long"));
+        assert!(optimizers
+            .iter()
+            .any(|(_, name)| *name == "Long rigid body based"));
+        assert!(!optimizers.iter().any(|(_, name)| *name == PRESETS[0].2 .1));
+        assert_eq!(optimizers.len(), FINAL_OPTIMIZERS.len());
+    }
+
+    #[cfg(feature = "physics")]
+    #[test]
+    fn test_pillar_shadow_covers_stage_behind_pillar() {
+        use crate::io::Pillar;
+        let task = Task {
+            room_width: 400.0,
+            room_height: 400.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 100.0,
+            stage_height: 100.0,
+            stage_bottom_left: (200.0, 150.0),
+            musicians: vec![0],
+            attendees: vec![],
+            pillars: vec![],
+        };
+        let pillar = Pillar {
+            center: (100.0, 200.0),
+            radius: 10.0,
+        };
+        let (polygon, center, dir) =
+            super::rap::pillar_shadow(&task, Point { x: 0.0, y: 200.0 }, &pillar).unwrap();
+        assert_eq!((center.x, center.y), (100.0, 200.0));
+        assert!((dir.x - 1.0).abs() < 1e-9 && dir.y.abs() < 1e-9);
+        // shadow reaches far side of the stage and widens with distance
+        assert!(polygon.iter().all(|p| p.x >= 100.0 - 1e-9));
+        assert!(polygon.iter().any(|p| p.x >= task.stage_right()));
+        let far_width = (polygon[2].y - polygon[3].y).abs();
+        assert!(far_width > 2.0 * pillar.radius);
+
+        // attendee inside of the pillar has no shadow
+        assert!(super::rap::pillar_shadow(&task, Point { x: 105.0, y: 200.0 }, &pillar).is_none());
+    }
+
+    #[cfg(feature = "physics")]
+    #[test]
+    fn test_pillar_aware_rapier() {
+        use crate::score::{calc, calc_visibility, Visibility};
+        use rand::SeedableRng;
+        let task = Task {
+            room_width: 400.0,
+            room_height: 400.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 100.0,
+            stage_height: 100.0,
+            stage_bottom_left: (150.0, 150.0),
+            musicians: vec![0, 0],
+            attendees: vec![Attendee {
+                x: 350.0,
+                y: 200.0,
+                tastes: vec![1000.0],
+            }],
+            pillars: vec![],
+        };
+        let solution = Solution {
+            placements: vec![Point { x: 170.0, y: 170.0 }, Point { x: 170.0, y: 230.0 }],
+            volumes: vec![1.0; 2],
+        };
+        let visibility = calc_visibility(&task, &solution);
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(42);
+        let (result, _) =
+            super::rap::pillar_aware_rapier_optimizer(&task, &solution, &visibility, &mut rng);
+        // without pillars it leaves the work to plain rapier optimizer
+        assert_eq!(result.placements, solution.placements);
+
+        // pillar hides the attendee from the bottom part of the stage
+        let mut task = task;
+        task.pillars.push(crate::io::Pillar {
+            center: (300.0, 180.0),
+            radius: 15.0,
+        });
+        let visibility = calc_visibility(&task, &solution);
+        let blocked = |visibility: &Visibility| {
+            (0..task.musicians.len())
+                .filter(|mus_idx| !visibility.is_visible(0, *mus_idx))
+                .count()
+        };
+        assert_eq!(blocked(&visibility), 1);
+        let before = calc(&task, &solution, &visibility).unwrap();
+        let (result, _) =
+            super::rap::pillar_aware_rapier_optimizer(&task, &solution, &visibility, &mut rng);
+        let result_visibility = calc_visibility(&task, &result);
+        assert_eq!(blocked(&result_visibility), 0);
+        assert!(calc(&task, &result, &result_visibility).unwrap() >= before);
+    }
+}
@@ -0,0 +1,423 @@
+use crate::analysis::{self, TaskStats};
+use crate::constructor::{self, Constructor};
+use crate::history::HistoryEntry;
+use crate::io::{Solution, Task, MUSICIAN_RADIUS};
+use crate::optimizer::{Optimizer, ALL_OPTIMIZERS, FINAL_OPTIMIZERS};
+use crate::storage::{read_task, task_exists};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+// problems with more attendees * musicians are considered big
+const BIG_TASK_PAIRS: usize = 500_000;
+// on small problems it is cheap to try more optimizers
+const SMALL_TASK_PAIRS: usize = 50_000;
+const PILLAR_BLOCKED_THRESHOLD: f64 = 0.05;
+const NEGATIVE_TASTES_THRESHOLD: f64 = 0.6;
+// less than this free space on stage means there is no room to move musicians around
+const CROWDED_STAGE_RATIO: f64 = 1.5;
+const SIMILAR_TASKS: usize = 5;
+const LEARNED_OPTIMIZERS: usize = 3;
+
+// Task features used to pick strategy and to find similar tasks
+#[derive(Serialize, Debug, Clone)]
+pub struct TaskFeatures {
+    pub pairs: usize,
+    pub instruments: usize,
+    pub narrow: bool,
+    pub stage_capacity_ratio: f64,
+    pub negative_taste_fraction: f64,
+    pub pillar_blocked_fraction: f64,
+}
+
+impl TaskFeatures {
+    pub fn new(task: &Task, stats: &TaskStats) -> Self {
+        TaskFeatures {
+            pairs: stats.attendees * stats.musicians,
+            instruments: stats.instruments,
+            narrow: task.stage_width.min(task.stage_height) < 4.0 * MUSICIAN_RADIUS,
+            stage_capacity_ratio: stats.stage_capacity_ratio,
+            negative_taste_fraction: stats.negative_taste_fraction,
+            pillar_blocked_fraction: stats.pillar_blocked_fraction,
+        }
+    }
+
+    pub fn for_task(task: &Task) -> Result<Self> {
+        Ok(Self::new(task, &analysis::analyse(task)?))
+    }
+
+    // Sizes are compared in log scale, fractions as is
+    pub fn dist(&self, other: &TaskFeatures) -> f64 {
+        let log_diff = |a: f64, b: f64| (a.max(1.0).ln() - b.max(1.0).ln()).abs();
+        log_diff(self.pairs as f64, other.pairs as f64) / 2.0
+            + log_diff(self.instruments as f64, other.instruments as f64)
+            + log_diff(self.stage_capacity_ratio, other.stage_capacity_ratio)
+            + (self.negative_taste_fraction - other.negative_taste_fraction).abs()
+            + 2.0 * (self.pillar_blocked_fraction - other.pillar_blocked_fraction).abs()
+            + if self.narrow == other.narrow {
+                0.0
+            } else {
+                2.0
+            }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Plan {
+    pub features: TaskFeatures,
+    // constructors in order of preference, first valid one should be used
+    pub constructors: Vec<Constructor>,
+    #[serde(serialize_with = "serialize_optimizer_names")]
+    pub optimizers: Vec<(Optimizer, &'static str)>,
+    pub reasons: Vec<String>,
+}
+
+fn serialize_optimizer_names<S: serde::Serializer>(
+    optimizers: &[(Optimizer, &'static str)],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(optimizers.iter().map(|(_, name)| name))
+}
+
+impl Plan {
+    // Builds solution with the first constructor which fits the stage
+    pub fn build(&self, task: &Task) -> Result<(Constructor, Solution)> {
+        constructor::build_with_fallback(task, &self.constructors)
+    }
+}
+
+// Problem features together with its history entries
+pub struct HistoryRecord {
+    pub problem: usize,
+    pub features: TaskFeatures,
+    pub entries: Vec<HistoryEntry>,
+}
+
+pub fn history_records(entries: &[HistoryEntry]) -> Vec<HistoryRecord> {
+    let mut by_problem: BTreeMap<usize, Vec<HistoryEntry>> = BTreeMap::new();
+    for entry in entries {
+        by_problem
+            .entry(entry.problem)
+            .or_default()
+            .push(entry.clone());
+    }
+    by_problem
+        .into_iter()
+        .filter(|(problem, _)| task_exists(*problem))
+        .filter_map(|(problem, entries)| {
+            let features = TaskFeatures::for_task(&read_task(problem)).ok()?;
+            Some(HistoryRecord {
+                problem,
+                features,
+                entries,
+            })
+        })
+        .collect()
+}
+
+fn find_optimizer(name: &str) -> Option<(Optimizer, &'static str)> {
+    ALL_OPTIMIZERS.iter().find(|(_, n)| *n == name).copied()
+}
+
+fn add_optimizer(optimizers: &mut Vec<(Optimizer, &'static str)>, name: &str) {
+    if optimizers.iter().any(|(_, n)| *n == name) {
+        return;
+    }
+    // optimizer may be disabled by features
+    if let Some(optimizer) = find_optimizer(name) {
+        optimizers.push(optimizer);
+    }
+}
+
+// Items with their credit, best first
+type Credits<T> = Vec<(T, f64)>;
+
+// History written before the constructor registry uses names of the old solvers
+fn constructor_from_source(source: &str) -> Option<Constructor> {
+    match source.strip_prefix("optimize ")? {
+        "dummy" => Some(Constructor::Grid),
+        "multi-dummy" => Some(Constructor::MultiSpread),
+        name => Constructor::from_name(name),
+    }
+}
+
+fn choose_constructors(features: &TaskFeatures, reasons: &mut Vec<String>) -> Vec<Constructor> {
+    if features.narrow {
+        reasons.push("narrow stage, musicians in zigzag".to_string());
+        vec![Constructor::Narrow, Constructor::Grid]
+    } else if features.negative_taste_fraction > NEGATIVE_TASTES_THRESHOLD {
+        // edge of the stage is the worst place when most tastes are negative
+        reasons.push("mostly negative tastes, spread over the whole stage".to_string());
+        vec![
+            Constructor::MultiSpread,
+            Constructor::BorderFirst,
+            Constructor::Hex,
+        ]
+    } else if features.stage_capacity_ratio < CROWDED_STAGE_RATIO {
+        reasons.push("crowded stage, dense packing from the border".to_string());
+        vec![
+            Constructor::BorderFirst,
+            Constructor::Hex,
+            Constructor::Grid,
+        ]
+    } else if features.pairs > BIG_TASK_PAIRS {
+        reasons.push("big task, cheap packing from the border".to_string());
+        vec![
+            Constructor::BorderFirst,
+            Constructor::Hex,
+            Constructor::Grid,
+        ]
+    } else {
+        reasons.push("border nearest to attendees first".to_string());
+        vec![
+            Constructor::BorderFirst,
+            Constructor::MultiSpread,
+            Constructor::Hex,
+        ]
+    }
+}
+
+// Credits optimizers and constructors from history of similar problems with relative score gain
+fn learn(
+    features: &TaskFeatures,
+    history: &[HistoryRecord],
+) -> (Credits<String>, Credits<Constructor>) {
+    let mut similar = history.iter().collect::<Vec<_>>();
+    similar.sort_by(|a, b| {
+        features
+            .dist(&a.features)
+            .partial_cmp(&features.dist(&b.features))
+            .unwrap()
+    });
+
+    let mut optimizer_credit: HashMap<String, f64> = HashMap::new();
+    let mut constructor_credit: HashMap<Constructor, f64> = HashMap::new();
+    for record in similar.into_iter().take(SIMILAR_TASKS) {
+        let weight = 1.0 / (1.0 + features.dist(&record.features));
+        for entry in &record.entries {
+            let gain = match entry.previous_score {
+                Some(prev) if entry.score > prev => {
+                    ((entry.score - prev) as f64 / prev.abs().max(1) as f64).min(1.0)
+                }
+                Some(_) => continue,
+                // first solution for the problem, no baseline to compare with
+                None => 1.0,
+            };
+            if let Some(constructor) = constructor_from_source(&entry.source) {
+                *constructor_credit.entry(constructor).or_default() += weight * gain;
+            }
+            if entry.chains.is_empty() {
+                continue;
+            }
+            let chain_gain = weight * gain / entry.chains.len() as f64;
+            for chain in &entry.chains {
+                let names = chain.split(" -> ").collect::<Vec<_>>();
+                for name in &names {
+                    *optimizer_credit.entry(name.to_string()).or_default() +=
+                        chain_gain / names.len() as f64;
+                }
+            }
+        }
+    }
+
+    let mut optimizers = optimizer_credit.into_iter().collect::<Vec<_>>();
+    optimizers.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+    let mut constructors = constructor_credit.into_iter().collect::<Vec<_>>();
+    constructors.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+    (optimizers, constructors)
+}
+
+pub fn plan(task: &Task, history: &[HistoryRecord]) -> Result<Plan> {
+    let features = TaskFeatures::for_task(task)?;
+    let mut reasons = vec![];
+
+    let mut constructors = choose_constructors(&features, &mut reasons);
+
+    let mut optimizers = FINAL_OPTIMIZERS.to_vec();
+    if features.pillar_blocked_fraction > PILLAR_BLOCKED_THRESHOLD {
+        reasons.push(format!(
+            "pillars hide {:.0}% of attendees",
+            features.pillar_blocked_fraction * 100.0
+        ));
+        add_optimizer(&mut optimizers, "Pillar aware rigid body based");
+        add_optimizer(&mut optimizers, "Silent musicians as blockers");
+    } else {
+        optimizers.retain(|(_, name)| *name != "Pillar aware rigid body based");
+    }
+    if features.negative_taste_fraction > NEGATIVE_TASTES_THRESHOLD {
+        reasons.push(format!(
+            "{:.0}% of tastes are negative",
+            features.negative_taste_fraction * 100.0
+        ));
+        add_optimizer(&mut optimizers, "Wrong taste away from audience");
+        add_optimizer(&mut optimizers, "Force based out of the way");
+    }
+    if features.stage_capacity_ratio < CROWDED_STAGE_RATIO {
+        // musicians can not move much, so only exchange places or rebuild parts of the stage
+        optimizers.retain(|(_, name)| !name.starts_with("Force based"));
+        add_optimizer(&mut optimizers, "Random swap positions");
+        add_optimizer(&mut optimizers, "Large neighbourhood search");
+    }
+    if features.pairs < SMALL_TASK_PAIRS {
+        reasons.push("small task, wider portfolio".to_string());
+        add_optimizer(&mut optimizers, "Random swap positions");
+        add_optimizer(&mut optimizers, "Random change positions");
+        add_optimizer(&mut optimizers, "Force based random walk");
+        add_optimizer(&mut optimizers, "Gradient ascent");
+        add_optimizer(&mut optimizers, "Differential evolution");
+    }
+
+    let (learned_optimizers, learned_constructors) = learn(&features, history);
+    let learned = learned_optimizers
+        .iter()
+        .filter(|(name, _)| !optimizers.iter().any(|(_, n)| n == name))
+        .filter_map(|(name, _)| find_optimizer(name))
+        .take(LEARNED_OPTIMIZERS)
+        .collect::<Vec<_>>();
+    for optimizer in learned {
+        reasons.push(format!("{} helped on similar tasks", optimizer.1));
+        optimizers.push(optimizer);
+    }
+    if let Some((constructor, _)) = learned_constructors
+        .iter()
+        .find(|(c, _)| constructors.contains(c))
+    {
+        if constructors[0] != *constructor {
+            reasons.push(format!(
+                "{} constructor helped on similar tasks",
+                constructor.name()
+            ));
+            constructors.retain(|c| c != constructor);
+            constructors.insert(0, *constructor);
+        }
+    }
+
+    Ok(Plan {
+        features,
+        constructors,
+        optimizers,
+        reasons,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        choose_constructors, constructor_from_source, learn, HistoryRecord, TaskFeatures,
+        BIG_TASK_PAIRS, CROWDED_STAGE_RATIO, NEGATIVE_TASTES_THRESHOLD,
+    };
+    use crate::constructor::Constructor;
+    use crate::history::HistoryEntry;
+
+    fn features(pairs: usize, pillar_blocked_fraction: f64) -> TaskFeatures {
+        TaskFeatures {
+            pairs,
+            instruments: 10,
+            narrow: false,
+            stage_capacity_ratio: 5.0,
+            negative_taste_fraction: 0.3,
+            pillar_blocked_fraction,
+        }
+    }
+
+    fn entry(problem: usize, score: i64, previous: i64, chains: &[&str]) -> HistoryEntry {
+        let mut entry = HistoryEntry::new(
+            problem,
+            score,
+            "optimize hex",
+            chains.iter().map(|c| c.to_string()).collect(),
+        );
+        entry.previous_score = Some(previous);
+        entry
+    }
+
+    #[test]
+    fn test_learn_prefers_similar_tasks() {
+        let history = vec![
+            HistoryRecord {
+                problem: 1,
+                features: features(1000, 0.0),
+                entries: vec![entry(1, 200, 100, &["Gradient ascent -> Greedy placement"])],
+            },
+            HistoryRecord {
+                problem: 2,
+                features: features(1_000_000, 0.5),
+                entries: vec![entry(2, 200, 100, &["Large neighbourhood search"])],
+            },
+        ];
+        let (optimizers, constructors) = learn(&features(1200, 0.0), &history);
+        // both optimizers of the chain get the same credit, ties are ordered by name
+        assert_eq!(optimizers[0].0, "Gradient ascent");
+        assert_eq!(optimizers[1].0, "Greedy placement");
+        assert_eq!(optimizers[2].0, "Large neighbourhood search");
+        assert_eq!(constructors[0].0, Constructor::Hex);
+
+        let (optimizers, _) = learn(&features(1_000_000, 0.5), &history);
+        assert_eq!(optimizers[0].0, "Large neighbourhood search");
+    }
+
+    #[test]
+    fn test_constructor_from_source() {
+        assert_eq!(
+            constructor_from_source("optimize dummy"),
+            Some(Constructor::Grid)
+        );
+        assert_eq!(
+            constructor_from_source("optimize multi-dummy"),
+            Some(Constructor::MultiSpread)
+        );
+        assert_eq!(
+            constructor_from_source("optimize narrow"),
+            Some(Constructor::Narrow)
+        );
+        assert_eq!(
+            constructor_from_source("optimize border"),
+            Some(Constructor::BorderFirst)
+        );
+        assert_eq!(constructor_from_source("optimize optimal"), None);
+        assert_eq!(constructor_from_source("ortools"), None);
+    }
+
+    #[test]
+    fn test_every_constructor_choice_has_reason() {
+        let narrow = TaskFeatures {
+            narrow: true,
+            ..features(1000, 0.0)
+        };
+        let negative = TaskFeatures {
+            negative_taste_fraction: NEGATIVE_TASTES_THRESHOLD + 0.1,
+            ..features(1000, 0.0)
+        };
+        let crowded = TaskFeatures {
+            stage_capacity_ratio: CROWDED_STAGE_RATIO / 2.0,
+            ..features(1000, 0.0)
+        };
+        let big = features(BIG_TASK_PAIRS + 1, 0.0);
+        let usual = features(1000, 0.0);
+
+        let mut first = vec![];
+        for features in [narrow, negative, crowded, big, usual] {
+            let mut reasons = vec![];
+            let constructors = choose_constructors(&features, &mut reasons);
+            assert_eq!(reasons.len(), 1);
+            first.push(constructors[0]);
+        }
+        assert_eq!(
+            first,
+            [
+                Constructor::Narrow,
+                Constructor::MultiSpread,
+                Constructor::BorderFirst,
+                Constructor::BorderFirst,
+                Constructor::BorderFirst,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_feature_dist() {
+        let a = features(1000, 0.0);
+        assert_eq!(a.dist(&a), 0.0);
+        assert!(a.dist(&features(2000, 0.0)) < a.dist(&features(2000, 0.5)));
+    }
+}
@@ -0,0 +1,265 @@
+use crate::history::HistoryEntry;
+use crate::io::{self, Task};
+use crate::score::{calc, calc_visibility_fast, potential_score, validate};
+use crate::solution::MAX_VOLUME;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProblemReport {
+    pub problem: usize,
+    pub musicians: usize,
+    pub instruments: usize,
+    pub attendees: usize,
+    pub pillars: usize,
+    // longer stage side to shorter one
+    pub stage_aspect_ratio: f64,
+    pub best_score: Option<i64>,
+    // solution directory name -> score, None if solution is missing or incorrect
+    pub historical_scores: BTreeMap<String, Option<i64>>,
+    // potential score with every musician at max volume, ignores pillars and q-factor
+    pub potential_score: i64,
+    // None for tasks with pillars, where q-factor can score above the potential
+    pub bound_gap: Option<i64>,
+    // what produced the best solution according to history
+    pub best_source: Option<String>,
+}
+
+fn score_file(task: &Task, path: &str) -> Option<i64> {
+    if !Path::new(path).exists() {
+        return None;
+    }
+    let solution = io::read_solution(path);
+    validate(task, &solution).ok()?;
+    let visibility = calc_visibility_fast(task, &solution);
+    calc(task, &solution, &visibility).ok()
+}
+
+fn dir_name(dir: &str) -> String {
+    Path::new(dir)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| dir.to_string())
+}
+
+pub fn problem_report(
+    problem: usize,
+    task: &Task,
+    best_dir: &str,
+    historical_dirs: &[String],
+    best_history: Option<&HistoryEntry>,
+) -> ProblemReport {
+    let best_score = score_file(task, &format!("{best_dir}/problem-{problem}.json"));
+    let historical_scores = historical_dirs
+        .iter()
+        .map(|dir| {
+            (
+                dir_name(dir),
+                score_file(task, &format!("{dir}/problem-{problem}.json")),
+            )
+        })
+        .collect();
+    let potential = (potential_score(task) as f64 * MAX_VOLUME) as i64;
+    let best_source = best_history
+        .filter(|entry| Some(entry.score) == best_score)
+        .map(|entry| {
+            if entry.chains.is_empty() {
+                entry.source.clone()
+            } else {
+                format!("{}: {}", entry.source, entry.chains.join("; "))
+            }
+        });
+
+    ProblemReport {
+        problem,
+        musicians: task.musicians.len(),
+        instruments: task.instruments_len(),
+        attendees: task.attendees.len(),
+        pillars: task.pillars.len(),
+        stage_aspect_ratio: task.stage_width.max(task.stage_height)
+            / task.stage_width.min(task.stage_height),
+        best_score,
+        historical_scores,
+        potential_score: potential,
+        bound_gap: best_score
+            .filter(|_| task.pillars.is_empty())
+            .map(|score| potential - score),
+        best_source,
+    }
+}
+
+fn score_cell(score: Option<i64>) -> String {
+    score.map(|s| s.to_string()).unwrap_or_default()
+}
+
+fn header(reports: &[ProblemReport]) -> Vec<String> {
+    let mut res = [
+        "problem",
+        "musicians",
+        "instruments",
+        "attendees",
+        "pillars",
+        "stage aspect",
+        "best score",
+    ]
+    .map(String::from)
+    .to_vec();
+    if let Some(report) = reports.first() {
+        res.extend(report.historical_scores.keys().cloned());
+    }
+    res.extend(["potential (no pillars)", "bound gap", "best source"].map(String::from));
+    res
+}
+
+fn rows(reports: &[ProblemReport]) -> Vec<Vec<String>> {
+    reports
+        .iter()
+        .map(|r| {
+            let mut row = vec![
+                r.problem.to_string(),
+                r.musicians.to_string(),
+                r.instruments.to_string(),
+                r.attendees.to_string(),
+                r.pillars.to_string(),
+                format!("{:.2}", r.stage_aspect_ratio),
+                score_cell(r.best_score),
+            ];
+            row.extend(r.historical_scores.values().map(|s| score_cell(*s)));
+            row.push(r.potential_score.to_string());
+            row.push(score_cell(r.bound_gap));
+            row.push(r.best_source.clone().unwrap_or_default());
+            row
+        })
+        .collect()
+}
+
+pub fn to_markdown(reports: &[ProblemReport]) -> String {
+    let header = header(reports);
+    let mut res = format!("| {} |\n", header.join(" | "));
+    res += &format!("|{}\n", "---|".repeat(header.len()));
+    for row in rows(reports) {
+        res += &format!("| {} |\n", row.join(" | "));
+    }
+    res
+}
+
+pub fn to_csv(reports: &[ProblemReport]) -> String {
+    let escape = |cell: &String| {
+        if cell.contains([',', '"', '\n']) {
+            format!("\"{}\"", cell.replace('"', "\"\""))
+        } else {
+            cell.clone()
+        }
+    };
+    let mut res = header(reports)
+        .iter()
+        .map(escape)
+        .collect::<Vec<_>>()
+        .join(",")
+        + "\n";
+    for row in rows(reports) {
+        res += &(row.iter().map(escape).collect::<Vec<_>>().join(",") + "\n");
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{problem_report, to_csv, to_markdown};
+    use crate::geom::Point;
+    use crate::history::HistoryEntry;
+    use crate::io::{self, Attendee, Pillar, Solution, Task};
+
+    fn task() -> Task {
+        Task {
+            room_width: 400.0,
+            room_height: 400.0,
+            room_bottom_left: (0.0, 0.0),
+            stage_width: 100.0,
+            stage_height: 50.0,
+            stage_bottom_left: (150.0, 150.0),
+            musicians: vec![0, 0],
+            attendees: vec![Attendee {
+                x: 200.0,
+                y: 100.0,
+                tastes: vec![1000.0],
+            }],
+            pillars: vec![],
+        }
+    }
+
+    #[test]
+    fn test_problem_report() {
+        let root = std::env::temp_dir().join(format!("report-test-{}", std::process::id()));
+        let best_dir = root.join("solutions");
+        let old_dir = root.join("solutions-20230708-124428");
+        let empty_dir = root.join("solutions-20230709-000000");
+        for dir in [&best_dir, &old_dir, &empty_dir] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        let (best_dir, old_dir, empty_dir) = (
+            best_dir.to_str().unwrap(),
+            old_dir.to_str().unwrap(),
+            empty_dir.to_str().unwrap(),
+        );
+
+        let task = task();
+        let best = Solution {
+            placements: vec![Point { x: 170.0, y: 160.0 }, Point { x: 200.0, y: 160.0 }],
+            volumes: vec![10.0; 2],
+        };
+        let old = Solution {
+            volumes: vec![1.0; 2],
+            ..best.clone()
+        };
+        io::write(&format!("{best_dir}/problem-3.json"), &best);
+        io::write(&format!("{old_dir}/problem-3.json"), &old);
+
+        let history = HistoryEntry::new(3, 0, "optimize border", vec!["Greedy".into()]);
+        let historical = [old_dir.to_string(), empty_dir.to_string()];
+        let mut report = problem_report(3, &task, best_dir, &historical, Some(&history));
+        // history entry does not describe this solution
+        assert_eq!(report.best_source, None);
+        let best_score = report.best_score.unwrap();
+        let history = HistoryEntry {
+            score: best_score,
+            ..history
+        };
+        report = problem_report(3, &task, best_dir, &historical, Some(&history));
+
+        // potential is not a bound with q-factor
+        let mut pillar_task = task.clone();
+        pillar_task.pillars.push(Pillar {
+            center: (50.0, 50.0),
+            radius: 5.0,
+        });
+        let pillar_report = problem_report(3, &pillar_task, best_dir, &[], None);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(pillar_report.best_score.is_some());
+        assert_eq!(pillar_report.bound_gap, None);
+
+        assert_eq!(report.musicians, 2);
+        assert_eq!(report.stage_aspect_ratio, 2.0);
+        assert_eq!(
+            report.historical_scores["solutions-20230708-124428"],
+            Some(best_score / 10)
+        );
+        assert_eq!(report.historical_scores["solutions-20230709-000000"], None);
+        assert_eq!(report.bound_gap, Some(report.potential_score - best_score));
+        assert_eq!(
+            report.best_source.as_deref(),
+            Some("optimize border: Greedy")
+        );
+
+        let markdown = to_markdown(&[report.clone()]);
+        let lines = markdown.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("solutions-20230708-124428"));
+        assert!(lines[2].starts_with("| 3 | 2 | 1 | 1 | 0 | 2.00 |"));
+
+        report.best_source = Some("a, \"b\"".into());
+        let csv = to_csv(&[report]);
+        assert!(csv.lines().nth(1).unwrap().ends_with(",\"a, \"\"b\"\"\""));
+    }
+}
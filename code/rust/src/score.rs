@@ -1,13 +1,17 @@
-use crate::geom::{Point, Segment};
+use crate::geom::{Point, Segment, Vector};
 use crate::io::{
     Attendee, ScoreExtended, Solution, Task, MUSICIAN_BLOCK_RADIUS, MUSICIAN_RADIUS, SCORE_CONST,
 };
 use anyhow::{bail, Result};
 use float_ord::FloatOrd;
 use rayon::prelude::*;
+use serde::Serialize;
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
-use std::sync::Mutex;
+use std::collections::BTreeSet;
+
+const TANGENT_EPS: f64 = 1e-12;
+// Fitness of invalid solutions in optimizers which compare scores
+pub const MIN_SCORE: i64 = -1_000_000_000_000;
 
 pub fn validate(task: &Task, solution: &Solution) -> Result<()> {
     let solution_length = solution.placements.len();
@@ -264,6 +268,38 @@ pub fn calc_ex(task: &Task, solution: &Solution, visibility: &Visibility) -> Sco
     }
 }
 
+#[derive(Serialize, Debug, Clone)]
+pub struct ScoreBreakdown {
+    pub score: i64,
+    pub instrument: Vec<i64>,
+    pub musician: Vec<i64>,
+    // (attendee, musician) pairs with something in between
+    pub blocked_pairs: usize,
+}
+
+pub fn calc_breakdown(task: &Task, solution: &Solution) -> Result<ScoreBreakdown> {
+    validate(task, solution)?;
+    let visibility = calc_visibility_fast(task, solution);
+    let score = calc_ex(task, solution, &visibility);
+
+    let mut instrument = vec![0; task.instruments_len()];
+    for (mus_idx, points) in score.musician.iter().enumerate() {
+        instrument[task.musicians[mus_idx]] += points;
+    }
+    let blocked_pairs = visibility
+        .visibility
+        .iter()
+        .map(|row| row.iter().filter(|v| !**v).count())
+        .sum();
+
+    Ok(ScoreBreakdown {
+        score: score.score,
+        instrument,
+        musician: score.musician,
+        blocked_pairs,
+    })
+}
+
 pub fn potential_score(task: &Task) -> i64 {
     let left_bottom = Point {
         x: task.stage_left(),
@@ -336,16 +372,102 @@ fn get_angle_comparator(p0: Point) -> impl FnMut(&Point, &Point) -> Ordering {
     }
 }
 
-pub fn calc_visibility_fast(task: &Task, solution: &Solution) -> Visibility {
-    let mut result = Mutex::new(vec![vec![true; task.musicians.len()]; task.attendees.len()]);
+// Angular sweep around pos, obstacles are given relative to pos. Returns visibility by attendee index.
+fn sweep_visibility(task: &Task, pos: Point, obstacles: Vec<(Vector, f64)>) -> Vec<bool> {
+    let mut result = vec![true; task.attendees.len()];
+    let mut crossing_zero = BTreeSet::new();
+    let max_radius = obstacles.iter().map(|(_, r)| *r).fold(0.0, f64::max);
+
+    let mut events = obstacles
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, (p, r))| {
+            let d = p.norm();
+            // exactly tangent line of sight is not blocked, shrink the cone to beat rounding errors
+            let alpha = (r / d).asin() - TANGENT_EPS;
+            let theta = p.atan2();
+            let mut a1 = theta - alpha;
+            if a1 < -std::f64::consts::PI {
+                a1 += std::f64::consts::TAU;
+            }
+            let mut a2 = theta + alpha;
+            if a2 > std::f64::consts::PI {
+                a2 -= std::f64::consts::TAU;
+            }
+
+            if a2 < a1 {
+                crossing_zero.insert((FloatOrd(d), idx));
+            }
+            vec![(a1, true, d, idx), (a2, false, d, idx)].into_iter()
+        })
+        .collect::<Vec<_>>();
+    events.sort_by(|(a, _, _, _), (b, _, _, _)| a.partial_cmp(b).unwrap());
 
-    solution
+    let mut attendees = task
+        .attendees
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            let v_from_pos = a.coord() - pos;
+            let angle = v_from_pos.atan2();
+            (i, angle, v_from_pos)
+        })
+        .collect::<Vec<_>>();
+    attendees.sort_by(|(_, a, _), (_, b, _)| a.partial_cmp(b).unwrap());
+
+    // obstacles which cover current angle, by distance of their centers
+    let mut entered = crossing_zero;
+    let mut events = events.into_iter().peekable();
+    let origin = Point { x: 0.0, y: 0.0 };
+
+    for (att_index, att_angle, att_v) in attendees {
+        while let Some(&(event_angle, enter, d, idx)) = events.peek() {
+            if event_angle > att_angle {
+                break;
+            }
+            if enter {
+                entered.insert((FloatOrd(d), idx));
+            } else {
+                entered.remove(&(FloatOrd(d), idx));
+            }
+            events.next();
+        }
+
+        let att_dist = att_v.norm();
+        let has_close_obstacle = entered
+            .range(..=(FloatOrd(att_dist), usize::MAX))
+            .next()
+            .is_some();
+        // obstacle center can be a bit farther than attendee while obstacle still covers line of sight
+        let has_overlapping_obstacle = || {
+            let line_of_sight = Segment {
+                from: origin,
+                to: origin + att_v,
+            };
+            entered
+                .range(
+                    (FloatOrd(att_dist), usize::MAX)
+                        ..=(FloatOrd(att_dist + max_radius), usize::MAX),
+                )
+                .any(|(_, idx)| {
+                    let (p, r) = obstacles[*idx];
+                    line_of_sight.dist(origin + p) < r
+                })
+        };
+        result[att_index] = !has_close_obstacle && !has_overlapping_obstacle();
+    }
+
+    result
+}
+
+pub fn calc_visibility_fast(task: &Task, solution: &Solution) -> Visibility {
+    let by_position = solution
         .placements
         .par_iter()
         .enumerate()
-        .for_each(|(pos_index, pos)| {
+        .map(|(pos_index, pos)| {
             // TODO pillars require more complex logic
-            let mut obstacles = solution.placements[0..pos_index]
+            let obstacles = solution.placements[0..pos_index]
                 .iter()
                 .map(|p| (*p, MUSICIAN_BLOCK_RADIUS))
                 .chain(
@@ -357,83 +479,126 @@ pub fn calc_visibility_fast(task: &Task, solution: &Solution) -> Visibility {
                 .map(|(p, r)| (p - *pos, r))
                 .collect::<Vec<_>>();
 
-            let mut crossing_zero_distances = BTreeMap::new();
-
-            let mut obstacles = obstacles
-                .into_iter()
-                .flat_map(|(p, r)| {
-                    let d = p.norm();
-                    let alpha = (r / d).asin();
-                    let theta = p.atan2();
-                    let mut a1 = theta - alpha;
-                    if a1 < -std::f64::consts::PI {
-                        a1 += std::f64::consts::TAU;
-                    }
-                    let mut a2 = theta + alpha;
-                    if a2 > std::f64::consts::PI {
-                        a2 -= std::f64::consts::TAU;
-                    }
+            sweep_visibility(task, *pos, obstacles)
+        })
+        .collect::<Vec<_>>();
 
-                    // if a1.is_nan() || a2.is_nan() {
-                    //     dbg!((pos_index, pos, p, r, d, alpha, theta, a1, a2, a2 < a1));
-                    // }
+    let mut result = vec![vec![true; task.musicians.len()]; task.attendees.len()];
+    for (pos_index, visible) in by_position.into_iter().enumerate() {
+        for (att_index, v) in visible.into_iter().enumerate() {
+            result[att_index][pos_index] = v;
+        }
+    }
 
-                    if a2 < a1 {
-                        *crossing_zero_distances.entry(FloatOrd(d)).or_insert(0) += 1;
-                    }
-                    vec![(a1, true, d), (a2, false, d)].into_iter()
-                })
-                .collect::<Vec<_>>();
-            obstacles.sort_by(|(a, _, _), (b, _, _)| a.partial_cmp(b).unwrap());
+    Visibility { visibility: result }
+}
 
-            let mut attendees = task
-                .attendees
+// Visibility of attendees from arbitrary points, blocked by given musicians and pillars.
+// Points do not block each other. Result is indexed by point, then by attendee.
+pub fn calc_points_visibility(task: &Task, blockers: &[Point], points: &[Point]) -> Vec<Vec<bool>> {
+    points
+        .par_iter()
+        .map(|pos| {
+            let obstacles = blockers
                 .iter()
-                .enumerate()
-                .map(|(i, a)| {
-                    let v_from_pos = a.coord() - *pos;
-                    let angle = v_from_pos.atan2();
-                    let d = v_from_pos.norm();
-                    (i, angle, d)
-                })
+                .map(|p| (*p, MUSICIAN_BLOCK_RADIUS))
+                .chain(task.pillars.iter().map(|p| (p.point(), p.radius)))
+                .map(|(p, r)| (p - *pos, r))
                 .collect::<Vec<_>>();
-            attendees.sort_by(|(_, a, _), (_, b, _)| a.partial_cmp(b).unwrap());
-
-            // keep that previous angle
-            let mut entered_distances = crossing_zero_distances;
-            let mut obstacles = obstacles.into_iter().peekable();
-
-            for (att_index, att_angle, att_dist) in attendees {
-                while obstacles.peek().is_some() {
-                    let (obstacle_angle, enter, obstacle_center_dist) = obstacles.peek().unwrap();
-                    if obstacle_angle <= &att_angle {
-                        let k = FloatOrd(*obstacle_center_dist);
-                        if *enter {
-                            *entered_distances.entry(k).or_insert(0) += 1;
-                        } else {
-                            let v = entered_distances.get_mut(&k).unwrap();
-                            *v -= 1;
-                            if *v == 0 {
-                                entered_distances.remove(&k);
-                            }
-                        }
-                        obstacles.next();
-                    } else {
-                        break;
+
+            sweep_visibility(task, *pos, obstacles)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calc_visibility, calc_visibility_fast};
+    use crate::geom::Point;
+    use crate::io::{Attendee, Pillar, Solution, Task, MUSICIAN_RADIUS};
+    use proptest::prelude::*;
+
+    const ROOM_SIZE: i32 = 400;
+    const STAGE_LEFT: i32 = 150;
+    const STAGE_SIZE: i32 = 100;
+
+    fn in_stage_margin(x: f64, y: f64, margin: f64) -> bool {
+        let (left, right) = (STAGE_LEFT as f64, (STAGE_LEFT + STAGE_SIZE) as f64);
+        x > left - margin && x < right + margin && y > left - margin && y < right + margin
+    }
+
+    // Integer coordinates like in real problems, so that collinear and tangent cases do happen.
+    // Attendees are never inside of obstacles, musicians are never inside of pillars.
+    fn task_and_solution() -> impl Strategy<Value = (Task, Solution)> {
+        let coord = 0..ROOM_SIZE;
+        let on_stage = STAGE_LEFT + 10..STAGE_LEFT + STAGE_SIZE - 10;
+        (
+            prop::collection::vec((on_stage.clone(), on_stage), 1..12),
+            prop::collection::vec((coord.clone(), coord.clone()), 1..40),
+            prop::collection::vec((coord.clone(), coord, 1..40), 0..6),
+        )
+            .prop_map(|(musicians, attendees, pillars)| {
+                let pillars = pillars
+                    .into_iter()
+                    .map(|(x, y, r)| Pillar {
+                        center: (x as f64, y as f64),
+                        radius: r as f64,
+                    })
+                    .filter(|p| !in_stage_margin(p.center.0, p.center.1, p.radius))
+                    .collect::<Vec<_>>();
+
+                let mut placements: Vec<Point> = vec![];
+                for (x, y) in musicians {
+                    let p = Point {
+                        x: x as f64,
+                        y: y as f64,
+                    };
+                    if placements
+                        .iter()
+                        .all(|other| other.dist(p) >= MUSICIAN_RADIUS)
+                    {
+                        placements.push(p);
                     }
                 }
-                let has_close_obstacle = entered_distances
-                    .range(..=FloatOrd(att_dist))
-                    .next()
-                    .is_some();
-                {
-                    let mut result = result.lock().unwrap();
-                    result[att_index][pos_index] = !has_close_obstacle;
-                }
-            }
-        });
 
-    Visibility {
-        visibility: result.into_inner().unwrap(),
+                let attendees = attendees
+                    .into_iter()
+                    .map(|(x, y)| Attendee {
+                        x: x as f64,
+                        y: y as f64,
+                        tastes: vec![1.0],
+                    })
+                    .filter(|a| !in_stage_margin(a.x, a.y, MUSICIAN_RADIUS))
+                    .filter(|a| pillars.iter().all(|p| a.coord().dist(p.point()) > p.radius))
+                    .collect::<Vec<_>>();
+
+                let task = Task {
+                    room_width: ROOM_SIZE as f64,
+                    room_height: ROOM_SIZE as f64,
+                    room_bottom_left: (0.0, 0.0),
+                    stage_width: STAGE_SIZE as f64,
+                    stage_height: STAGE_SIZE as f64,
+                    stage_bottom_left: (STAGE_LEFT as f64, STAGE_LEFT as f64),
+                    musicians: vec![0; placements.len()],
+                    attendees,
+                    pillars,
+                };
+                let solution = Solution {
+                    volumes: vec![1.0; placements.len()],
+                    placements,
+                };
+                (task, solution)
+            })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn prop_fast_visibility_matches_brute_force((task, solution) in task_and_solution()) {
+            let expected = calc_visibility(&task, &solution);
+            let actual = calc_visibility_fast(&task, &solution);
+            prop_assert_eq!(expected.visibility, actual.visibility);
+        }
     }
 }
@@ -1,6 +1,6 @@
 use crate::geom::Point;
-use crate::io::{Solution, Task, MUSICIAN_RADIUS};
-use crate::optimizer::optimize_placements_greedy;
+use crate::io::{default_volumes_task, Solution, Task, MUSICIAN_RADIUS};
+use crate::optimizer::{legalize_placements, optimize_placements_greedy};
 use crate::score;
 use crate::score::Visibility;
 
@@ -92,6 +92,118 @@ pub fn transposer_solver(s: impl Fn(&Task) -> Solution) -> impl Fn(&Task) -> Sol
     }
 }
 
+// Runs solver in canonical orientation of the task and maps the result back
+pub fn canonical_solver(s: impl Fn(&Task) -> Solution) -> impl Fn(&Task) -> Solution {
+    move |task| {
+        let transform = task.canonical_transform();
+        let solution = s(&task.transform(&transform));
+        let mut solution = solution.transform(&transform.inverse());
+        // undo float errors on stage borders and between neighbours, callers validate the result
+        let _ = legalize_placements(task, &mut solution);
+        solution
+    }
+}
+
+// musicians are allowed to stand exactly at radius distance, small gap protects from float errors
+const BORDER_SLOT_SPACING: f64 = MUSICIAN_RADIUS + 1e-6;
+
+// How much attendees could like musician in this place, ignoring blocking
+fn slot_proximity(task: &Task, best_tastes: &[f64], p: Point) -> f64 {
+    task.attendees
+        .iter()
+        .zip(best_tastes)
+        .map(|(a, taste)| taste / p.dist_sqr(a.coord()))
+        .sum()
+}
+
+// Fills stage perimeter first, places closer to attendees go first, then inner rings.
+// Instruments are assigned to slots greedily by score.
+pub fn border_first(task: &Task) -> anyhow::Result<Solution> {
+    let (left, right) = (
+        task.stage_left() + MUSICIAN_RADIUS,
+        task.stage_right() - MUSICIAN_RADIUS,
+    );
+    let (bottom, top) = (
+        task.stage_bottom() + MUSICIAN_RADIUS,
+        task.stage_top() - MUSICIAN_RADIUS,
+    );
+    if left > right || bottom > top {
+        anyhow::bail!("Stage is too small for any musician");
+    }
+    let best_tastes = task
+        .attendees
+        .iter()
+        .map(|a| a.tastes.iter().copied().fold(0.0, f64::max))
+        .collect::<Vec<_>>();
+
+    let row_step = BORDER_SLOT_SPACING * 60.0f64.to_radians().sin();
+    let mut slots: Vec<Point> = vec![];
+    let mut row = 0;
+    while slots.len() < task.musicians.len() {
+        let inset = row as f64 * row_step;
+        if left + inset > right - inset && bottom + inset > top - inset {
+            anyhow::bail!(
+                "Only {} of {} musicians fit the stage",
+                slots.len(),
+                task.musicians.len()
+            );
+        }
+        // odd rings are shifted by half a step along the edge, counting from the outer ring
+        // corner, so that neighbour rings form hex lattice
+        let shift = if row % 2 == 1 {
+            BORDER_SLOT_SPACING / 2.0
+        } else {
+            0.0
+        };
+        let first_on_lattice = |from: f64| {
+            let steps = ((inset - shift) / BORDER_SLOT_SPACING - 1e-9)
+                .ceil()
+                .max(0.0);
+            from + shift + steps * BORDER_SLOT_SPACING
+        };
+        let (row_left, row_right) = (left + inset, (right - inset).max(left + inset));
+        let (row_bottom, row_top) = (bottom + inset, (top - inset).max(bottom + inset));
+
+        let mut row_slots = vec![];
+        let mut x = first_on_lattice(left);
+        while x <= row_right {
+            row_slots.push(Point { x, y: row_bottom });
+            row_slots.push(Point { x, y: row_top });
+            x += BORDER_SLOT_SPACING;
+        }
+        let mut y = first_on_lattice(bottom);
+        while y <= row_top {
+            row_slots.push(Point { x: row_left, y });
+            row_slots.push(Point { x: row_right, y });
+            y += BORDER_SLOT_SPACING;
+        }
+
+        let mut row_slots = row_slots
+            .into_iter()
+            .map(|p| (slot_proximity(task, &best_tastes, p), p))
+            .collect::<Vec<_>>();
+        row_slots.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        for (_, p) in row_slots {
+            if slots.len() == task.musicians.len() {
+                break;
+            }
+            // lattice neighbours are a bit more than radius apart after float errors
+            if slots.iter().all(|s| s.dist(p) >= MUSICIAN_RADIUS) {
+                slots.push(p);
+            }
+        }
+        row += 1;
+    }
+
+    let solution = Solution {
+        placements: slots,
+        volumes: default_volumes_task(task),
+    };
+    let visibility = score::calc_visibility_fast(task, &solution);
+    let (solution, _) = optimize_placements_greedy(task, &solution, &visibility);
+    Ok(solution)
+}
+
 fn dummy_opti_solver(task: &Task, spread: f64, scale_to_stage: bool) -> anyhow::Result<Solution> {
     let solution = dummy_hex(task, spread, scale_to_stage);
 
@@ -102,7 +214,7 @@ fn dummy_opti_solver(task: &Task, spread: f64, scale_to_stage: bool) -> anyhow::
     Ok(solution)
 }
 
-pub fn multi_dummy_solver(task: &Task) -> Solution {
+pub fn multi_dummy_solver(task: &Task) -> anyhow::Result<Solution> {
     const SPHERE_PACKING_CONST: f64 = 0.9069;
     let mut spreads =
         if task.attendees.len() * task.musicians.len() * task.musicians.len() > 1863225000 {
@@ -137,11 +249,11 @@ pub fn multi_dummy_solver(task: &Task) -> Solution {
             let visibility = score::calc_visibility(&task, &solution);
             score::calc(&task, &solution, &visibility).unwrap_or(0)
         })
-        .expect("No solutions found")
+        .ok_or_else(|| anyhow::anyhow!("No spread of hex layout fits the stage"))
 }
 
 const MIN_VOLUME: f64 = 0.0;
-const MAX_VOLUME: f64 = 10.0;
+pub const MAX_VOLUME: f64 = 10.0;
 
 pub fn recalc_volumes(task: &Task, solution: &mut Solution, visibility: &Visibility) {
     solution.volumes = solution
@@ -0,0 +1,149 @@
+use crate::history::{self, HistoryEntry, HISTORY_FILE};
+use crate::io::{self, Solution, Task};
+use crate::score;
+use crate::solution::dummy;
+
+pub const DATA_DIR: &str = "../../data";
+pub const BASE_SOLUTIONS_DIR: &str = "../../solutions-20230708-124428";
+pub const OPTIMAL_SOLUTIONS_DIR: &str = "../../solutions";
+pub const MANUAL_SOLUTIONS_DIR: &str = "../../solutions-manual";
+pub const TASKS_NUM: usize = 90;
+pub const TOKEN_FILE: &str = "../../token";
+pub const SUBMISSIONS_LOG: &str = "../../submissions.jsonl";
+pub const SUBMIT_QUEUE: &str = "../../submit-queue.jsonl";
+
+pub fn get_solution(task: &Task, solution_path: &str) -> Solution {
+    if std::fs::metadata(solution_path).is_ok() {
+        io::read_solution(solution_path)
+    } else {
+        let sol = dummy(task);
+        io::write(solution_path, &sol);
+        sol
+    }
+}
+
+pub fn get_base_solution(task: &Task, i: usize) -> Solution {
+    get_solution(task, &format!("{BASE_SOLUTIONS_DIR}/problem-{i}.json"))
+}
+
+pub fn get_optimal_solution(task: &Task, i: usize) -> Solution {
+    get_solution(task, &format!("{OPTIMAL_SOLUTIONS_DIR}/problem-{i}.json"))
+}
+
+pub fn get_manual_solution(task: &Task, i: usize) -> Solution {
+    let solution_path = format!("{MANUAL_SOLUTIONS_DIR}/problem-{i}.json");
+    if std::fs::metadata(&solution_path).is_ok() {
+        io::read_solution(&solution_path)
+    } else {
+        get_optimal_solution(task, i)
+    }
+}
+
+pub fn read_task(i: usize) -> Task {
+    io::read(&format!("{DATA_DIR}/problem-{i}.json"))
+}
+
+// Writes solution if it is better than the best known one and records it in history.
+// Source and chains describe what produced the solution.
+pub fn write_optimal_solution(
+    task: &Task,
+    solution: &Solution,
+    points: i64,
+    i: usize,
+    source: &str,
+    chains: Vec<String>,
+) {
+    let cur_solution = get_optimal_solution(task, i);
+    let visibility = score::calc_visibility(task, &cur_solution);
+
+    let previous_score = match score::calc(task, &cur_solution, &visibility) {
+        Ok(cur_points) => {
+            if cur_points > points {
+                println!(
+                    "Solution for task {i} was not improved (currently {cur_points}, updated {points})"
+                );
+                return;
+            } else if cur_points == points {
+                println!("Solution for task {i} did not change");
+                return;
+            } else {
+                println!("+++Solution for task {i} was improved from {cur_points} to {points}");
+            }
+            Some(cur_points)
+        }
+        Err(_) => {
+            println!("Solution for task {i} was incorrect, got {points} points");
+            None
+        }
+    };
+
+    io::write(
+        &format!("{OPTIMAL_SOLUTIONS_DIR}/problem-{i}.json"),
+        solution,
+    );
+
+    let mut entry = HistoryEntry::new(i, points, source, chains);
+    entry.previous_score = previous_score;
+    if let Err(err) = history::append(HISTORY_FILE, &entry) {
+        println!("Could not write history for task {i}: {err}");
+    }
+}
+
+pub fn write_manual_solution(solution: &Solution, i: usize) {
+    io::write(
+        &format!("{MANUAL_SOLUTIONS_DIR}/problem-{i}.json"),
+        solution,
+    );
+}
+
+// Snapshot dirs are named by time they were taken, like solutions-20230708-124428
+fn is_snapshot_dir(name: &str) -> bool {
+    let Some(stamp) = name.strip_prefix("solutions-") else {
+        return false;
+    };
+    let bytes = stamp.as_bytes();
+    bytes.len() == 15
+        && bytes[8] == b'-'
+        && bytes
+            .iter()
+            .enumerate()
+            .all(|(idx, b)| idx == 8 || b.is_ascii_digit())
+}
+
+// Older snapshots of solutions which live next to the best solutions, sorted by name
+pub fn historical_solution_dirs() -> Vec<String> {
+    let root = std::path::Path::new(OPTIMAL_SOLUTIONS_DIR)
+        .parent()
+        .expect("Solutions dir should have parent");
+    let mut dirs = std::fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .filter(|entry| is_snapshot_dir(&entry.file_name().to_string_lossy()))
+                .map(|entry| entry.path().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+pub fn task_exists(i: usize) -> bool {
+    std::path::Path::new(&format!("{DATA_DIR}/problem-{i}.json")).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_snapshot_dir;
+
+    #[test]
+    fn test_snapshot_dir_names() {
+        assert!(is_snapshot_dir("solutions-20230708-124428"));
+        assert!(!is_snapshot_dir("solutions-manual"));
+        assert!(!is_snapshot_dir("solutions"));
+        assert!(!is_snapshot_dir("solutions-20230708"));
+        assert!(!is_snapshot_dir("solutions-20230708-124428-old"));
+        assert!(!is_snapshot_dir("solutions-2023070a-124428"));
+    }
+}
@@ -0,0 +1,64 @@
+use icfpc2023::io;
+use icfpc2023::score::{calc, calc_ex, calc_visibility, calc_visibility_fast};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// Official scores, not generated by our scorer, see "source" in the file
+const GOLDEN_SCORES_FILE: &str = "tests/fixtures/golden-scores.json";
+// Tasks from the specification, all scored with the same sample solution
+const SPEC_DIR: &str = "tests/fixtures/spec";
+// Frozen copies of solutions, so that improving ../../solutions does not break the test
+const GOLDEN_SOLUTIONS_DIR: &str = "tests/fixtures/solutions";
+// Small problems, both lightning and with pillars, so that the slow scorer stays fast enough
+const GOLDEN_PROBLEMS: [usize; 10] = [16, 22, 41, 42, 43, 47, 51, 55, 56, 85];
+
+#[derive(Deserialize)]
+struct GoldenScores {
+    source: String,
+    scores: BTreeMap<String, i64>,
+}
+
+fn golden_scores() -> GoldenScores {
+    let data = std::fs::read_to_string(GOLDEN_SCORES_FILE)
+        .unwrap_or_else(|_| panic!("Unable to read file {GOLDEN_SCORES_FILE}"));
+    serde_json::from_str(&data).expect("Could not parse golden scores")
+}
+
+#[test]
+fn official_scores_match() {
+    let golden = golden_scores();
+    assert!(!golden.scores.is_empty());
+    let solution = io::read_solution(&format!("{SPEC_DIR}/sample-solution.json"));
+    for (name, expected) in &golden.scores {
+        let task = io::read(&format!("{SPEC_DIR}/{name}.json"));
+        let visibility = calc_visibility(&task, &solution);
+        let score = calc(&task, &solution, &visibility).expect("Sample solution is invalid");
+        assert_eq!(
+            score, *expected,
+            "Score for {name} differs from the official one ({})",
+            golden.source
+        );
+    }
+}
+
+// Contest problems have no official scores for our solutions, so scorers are checked against each other
+#[test]
+fn scorers_agree_on_stored_solutions() {
+    for i in GOLDEN_PROBLEMS {
+        let task = io::read(&format!("../../data/problem-{i}.json"));
+        let solution = io::read_solution(&format!("{GOLDEN_SOLUTIONS_DIR}/problem-{i}.json"));
+
+        let visibility = calc_visibility(&task, &solution);
+        let fast_visibility = calc_visibility_fast(&task, &solution);
+        assert_eq!(
+            visibility.visibility, fast_visibility.visibility,
+            "Fast visibility differs for problem {i}"
+        );
+
+        let score = calc(&task, &solution, &visibility).expect("Stored solution is invalid");
+        let score_ex = calc_ex(&task, &solution, &visibility);
+        assert_eq!(score_ex.score, score, "calc_ex differs for problem {i}");
+        assert_eq!(score_ex.musician.iter().sum::<i64>(), score);
+        assert_eq!(score_ex.attendee.iter().sum::<i64>(), score);
+    }
+}
@@ -0,0 +1,117 @@
+#![cfg(all(feature = "contest", feature = "server"))]
+
+use icfpc2023::contest::{read_log, read_queue};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output};
+use std::thread::sleep;
+use std::time::Duration;
+
+const SOLUTION: &str = "tests/fixtures/solutions/problem-42.json";
+
+struct Mock {
+    child: Child,
+}
+
+impl Mock {
+    fn start(port: u16) -> Self {
+        // killed on drop, also when the test panics
+        let mock = Mock {
+            child: Command::new(env!("CARGO_BIN_EXE_mock-contest"))
+                .arg(port.to_string())
+                .spawn()
+                .expect("Could not start mock contest"),
+        };
+        for _ in 0..100 {
+            if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                return mock;
+            }
+            sleep(Duration::from_millis(50));
+        }
+        panic!("Mock contest did not start");
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+fn submit(dir: &Path, port: u16, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rust"))
+        .arg("submit")
+        .args(args)
+        .arg("--base-url")
+        .arg(format!("http://127.0.0.1:{port}"))
+        .arg("--token")
+        .arg(dir.join("token"))
+        .arg("--log")
+        .arg(dir.join("submissions.jsonl"))
+        .arg("--queue")
+        .arg(dir.join("queue.jsonl"))
+        .args(["--interval-ms", "0"])
+        .output()
+        .expect("Could not run submit")
+}
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("submit-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("token"), "test-token").unwrap();
+    dir
+}
+
+#[test]
+fn submit_to_mock_contest() {
+    let dir = temp_dir();
+    let log = dir.join("submissions.jsonl");
+    let queue = dir.join("queue.jsonl");
+    let port = free_port();
+
+    // --solution alone would send the same file for every problem
+    let out = submit(&dir, port, &["--solution", SOLUTION]);
+    assert!(!out.status.success());
+
+    // broken file is reported and skipped
+    let broken = dir.join("broken.json");
+    std::fs::write(&broken, "{\"placements\": [").unwrap();
+    let out = submit(&dir, port, &["42", "--solution", broken.to_str().unwrap()]);
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Could not parse solution"));
+    assert!(read_log(log.to_str().unwrap()).is_empty());
+
+    // api is down, solution stays in the queue
+    let out = submit(&dir, port, &["42", "--solution", SOLUTION]);
+    assert!(out.status.success());
+    let log_entries = read_log(log.to_str().unwrap());
+    assert_eq!(log_entries.len(), 1);
+    assert_eq!(log_entries[0].status, None);
+    assert_eq!(read_queue(queue.to_str().unwrap()).len(), 1);
+
+    let mock = Mock::start(port);
+    let out = submit(&dir, port, &["42", "--solution", SOLUTION]);
+    assert!(out.status.success());
+    let log_entries = read_log(log.to_str().unwrap());
+    assert_eq!(log_entries.len(), 2);
+    assert!(log_entries[1].is_success());
+    assert_eq!(log_entries[1].problem, 42);
+    assert!(read_queue(queue.to_str().unwrap()).is_empty());
+
+    // accepted solution is not sent again
+    let out = submit(&dir, port, &["42", "--solution", SOLUTION]);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("already submitted"));
+    assert_eq!(read_log(log.to_str().unwrap()).len(), 2);
+
+    drop(mock);
+    std::fs::remove_dir_all(&dir).unwrap();
+}